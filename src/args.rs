@@ -1,9 +1,22 @@
-use clap::{Parser, Subcommand};
+use clap::{Args, Parser, Subcommand};
+use solana_sdk::hash::Hash;
+
+use crate::{constants::BENCHMARK_WARMUP_DURATION, send::TxEncoding, utils::CoreSelection};
 
 #[derive(Parser, Debug)]
 pub struct AccountArgs {
-    #[arg(value_name = "ADDRESS", help = "The address to the account to fetch.")]
-    pub address: Option<String>,
+    #[arg(
+        value_name = "ADDRESS",
+        help = "The addresses of the accounts to fetch. Several addresses print one summary table."
+    )]
+    pub addresses: Vec<String>,
+
+    #[arg(
+        long,
+        value_name = "PATH",
+        help = "A file of account addresses to summarize, one per line. Lines starting with # are skipped."
+    )]
+    pub file: Option<String>,
 
     #[arg(
         short,
@@ -13,6 +26,15 @@ pub struct AccountArgs {
     )]
     pub proof: Option<String>,
 
+    #[arg(
+        long,
+        value_name = "SECONDS",
+        num_args = 0..=1,
+        default_missing_value = "10",
+        help = "Keep the table on screen, refreshing it every SECONDS (10 by default) and highlighting changes. Put the ADDRESS before --watch."
+    )]
+    pub watch: Option<u64>,
+
     #[command(subcommand)]
     pub command: Option<AccountCommand>,
 }
@@ -20,7 +42,19 @@ pub struct AccountArgs {
 #[derive(Subcommand, Clone, Debug)]
 pub enum AccountCommand {
     #[command(about = "Close an account and reclaim rent.")]
-    Close,
+    Close(AccountCloseArgs),
+}
+
+#[derive(Parser, Clone, Debug)]
+pub struct AccountCloseArgs {
+    #[arg(
+        long,
+        help = "Sign with your durable nonce account instead of a recent blockhash."
+    )]
+    pub nonce: bool,
+
+    #[command(flatten)]
+    pub offline: OfflineArgs,
 }
 
 #[derive(Parser, Debug, Clone)]
@@ -28,11 +62,14 @@ pub struct CollectArgs {
     #[arg(
         long,
         short,
-        value_name = "CORES_COUNT",
-        help = "The number of CPU cores to allocate to collecting.",
-        default_value_t = (num_cpus::get() - 1).max(1).to_string()
+        value_name = "CORES",
+        help = "The CPU cores to allocate to collecting: a count, ALL, or a list of core ids like 0-7,16-23.",
+        default_value_t = CoreSelection::Count((num_cpus::get() - 1).max(1))
     )]
-    pub cores: String,
+    pub cores: CoreSelection,
+
+    #[command(flatten)]
+    pub core_options: CoreOptionArgs,
 
     #[arg(
         long,
@@ -52,6 +89,21 @@ pub struct CollectArgs {
     )]
     pub min_difficulty: u32,
 
+    #[arg(
+        long,
+        value_name = "DIFFICULTY",
+        help = "Submit as soon as a hash reaches this difficulty, instead of hashing until the cutoff. Solo collecting only."
+    )]
+    pub target_difficulty: Option<u32>,
+
+    #[arg(
+        long,
+        value_name = "SECONDS",
+        help = "With --target-difficulty, the minimum seconds to hash before submitting early. Rewards depend on the timing of a submission.",
+        default_value = "0"
+    )]
+    pub min_elapsed: u64,
+
     #[arg(
         long,
         short,
@@ -68,6 +120,20 @@ pub struct CollectArgs {
     )]
     pub pool_url: Option<String>,
 
+    #[arg(
+        long,
+        value_name = "DIR_OR_LIST",
+        help = "Collect for every keypair in a directory, or in a comma separated list of keypair files. The cores are split between them."
+    )]
+    pub keypairs: Option<String>,
+
+    #[arg(
+        long,
+        value_name = "ADDRESS",
+        help = "Serve Prometheus metrics at http://ADDRESS/metrics, e.g. 0.0.0.0:9100."
+    )]
+    pub metrics_addr: Option<String>,
+
     #[arg(
         long,
         short,
@@ -82,11 +148,129 @@ pub struct BenchmarkArgs {
     #[arg(
         long,
         short,
-        value_name = "THREAD_COUNT",
-        help = "The number of cores to use during the benchmark",
-        default_value = "1"
+        value_name = "CORES",
+        help = "The cores to use during the benchmark: a count, ALL, or a list of core ids like 0-7,16-23. Defaults to 1, or to every core with --sweep."
+    )]
+    pub cores: Option<CoreSelection>,
+
+    #[command(flatten)]
+    pub core_options: CoreOptionArgs,
+
+    #[arg(
+        long,
+        value_name = "SECONDS",
+        help = "How long to benchmark each hash function, or each trial with --sweep. Defaults to 30, or to 15 with --sweep."
+    )]
+    pub duration: Option<u64>,
+
+    #[arg(
+        long,
+        value_name = "SECONDS",
+        help = "Seconds at the start of each run left out of the warm averages.",
+        default_value_t = BENCHMARK_WARMUP_DURATION
+    )]
+    pub warmup: u64,
+
+    #[arg(
+        long,
+        help = "Run a trial for every core count from 1 to the selected cores, pinned and unpinned, and recommend the --cores with the best throughput."
+    )]
+    pub sweep: bool,
+
+    #[arg(long, help = "Print the results as JSON.")]
+    pub json: bool,
+}
+
+#[derive(Parser, Debug)]
+pub struct EstimateArgs {
+    #[arg(
+        long,
+        value_name = "HASHES_PER_SECOND",
+        help = "The hashrate to estimate for. Measured on this machine if not given."
+    )]
+    pub hashrate: Option<f64>,
+
+    #[arg(
+        long,
+        short,
+        value_name = "CORES",
+        help = "The cores to measure the hashrate on: a count, ALL, or a list of core ids like 0-7,16-23.",
+        default_value_t = CoreSelection::Count((num_cpus::get() - 1).max(1))
+    )]
+    pub cores: CoreSelection,
+
+    #[command(flatten)]
+    pub core_options: CoreOptionArgs,
+
+    #[arg(
+        long,
+        value_name = "SECONDS",
+        help = "How long to measure the hashrate for.",
+        default_value = "15"
+    )]
+    pub duration: u64,
+
+    #[arg(
+        long,
+        short,
+        value_name = "SECONDS",
+        help = "The number seconds before the deadline collecting stops hashing and starts submitting.",
+        default_value = "5"
+    )]
+    pub buffer_time: u64,
+
+    #[arg(
+        long,
+        value_name = "COMPUTE_UNITS",
+        help = "The compute unit limit of a mine transaction, used to price its priority fee. Defaults to the compute units your last mine transaction used plus --compute-unit-margin, like collecting sets it."
+    )]
+    pub compute_unit_limit: Option<u32>,
+
+    #[arg(
+        long,
+        value_name = "ETH",
+        help = "The price of one BITZ in ETH, to find the break-even priority fee."
     )]
-    pub cores: String,
+    pub price: Option<f64>,
+}
+
+#[derive(Args, Debug, Clone)]
+pub struct CoreOptionArgs {
+    #[arg(
+        long,
+        help = "Use one hardware thread per physical core, skipping SMT (hyper-threading) siblings."
+    )]
+    pub skip_smt: bool,
+
+    #[arg(
+        long,
+        help = "Let the OS schedule the hashing threads instead of pinning each to its core."
+    )]
+    pub unpinned: bool,
+}
+
+#[derive(Parser, Debug)]
+pub struct HistoryArgs {
+    #[arg(
+        long,
+        value_name = "DATE",
+        help = "Start of the range, as YYYY-MM-DD or \"YYYY-MM-DD HH:MM:SS\" local time."
+    )]
+    pub from: Option<String>,
+
+    #[arg(
+        long,
+        value_name = "DATE",
+        help = "End of the range, as YYYY-MM-DD (inclusive) or \"YYYY-MM-DD HH:MM:SS\" local time."
+    )]
+    pub to: Option<String>,
+
+    #[arg(
+        long,
+        value_name = "ADDRESS",
+        help = "Only count transactions mined for this proof authority."
+    )]
+    pub authority: Option<String>,
 }
 
 #[derive(Parser, Debug)]
@@ -111,6 +295,15 @@ pub struct ClaimArgs {
         help = "The optional pool url to claim rewards from."
     )]
     pub pool_url: Option<String>,
+
+    #[arg(
+        long,
+        help = "Sign with your durable nonce account instead of a recent blockhash."
+    )]
+    pub nonce: bool,
+
+    #[command(flatten)]
+    pub offline: OfflineArgs,
 }
 
 #[derive(Clone, Parser, Debug)]
@@ -131,6 +324,16 @@ pub struct StakeArgs {
         help = "List the stake accounts of another authority."
     )]
     pub authority: Option<String>,
+
+    #[arg(
+        long,
+        help = "Sign with your durable nonce account instead of a recent blockhash.",
+        global = true
+    )]
+    pub nonce: bool,
+
+    #[command(flatten)]
+    pub offline: OfflineArgs,
 }
 
 #[derive(Subcommand, Clone, Debug)]
@@ -198,3 +401,96 @@ pub struct StakeWithdrawArgs {
 
 #[derive(Parser, Clone, Debug)]
 pub struct StakeAccountsArgs {}
+
+#[derive(Parser, Debug)]
+pub struct NonceArgs {
+    #[command(subcommand)]
+    pub command: Option<NonceCommand>,
+}
+
+#[derive(Subcommand, Clone, Debug)]
+pub enum NonceCommand {
+    #[command(about = "Create your durable nonce account.")]
+    Create,
+
+    #[command(about = "Show your durable nonce account.")]
+    Show,
+
+    #[command(about = "Withdraw ETH from your durable nonce account.")]
+    Withdraw(NonceWithdrawArgs),
+}
+
+#[derive(Parser, Clone, Debug)]
+pub struct NonceWithdrawArgs {
+    #[arg(
+        value_name = "AMOUNT",
+        help = "The amount of ETH to withdraw. Defaults to max, which closes the account."
+    )]
+    pub amount: Option<f64>,
+
+    #[arg(
+        long,
+        value_name = "WALLET_ADDRESS",
+        help = "Wallet address to receive the withdrawn ETH."
+    )]
+    pub to: Option<String>,
+}
+
+/// Flags for building transactions without broadcasting them.
+#[derive(Args, Clone, Debug, Default)]
+pub struct OfflineArgs {
+    #[arg(
+        long,
+        help = "Sign the transaction with your keypair and print it instead of sending it. Makes no RPC calls.",
+        requires = "blockhash",
+        global = true
+    )]
+    pub sign_only: bool,
+
+    #[arg(
+        long,
+        value_name = "BLOCKHASH",
+        help = "The blockhash to sign exported transactions with, instead of fetching one. With --nonce, the current value of your nonce account.",
+        global = true
+    )]
+    pub blockhash: Option<Hash>,
+
+    #[arg(
+        long,
+        value_name = "FILE",
+        help = "Write the transaction to FILE instead of sending it. Send it later with `bitz submit`.",
+        global = true
+    )]
+    pub export_tx: Option<String>,
+
+    #[arg(
+        long,
+        value_name = "ENCODING",
+        help = "The encoding of exported transactions.",
+        default_value = "base64",
+        global = true
+    )]
+    pub tx_encoding: TxEncoding,
+}
+
+#[derive(Parser, Debug)]
+pub struct SignArgs {
+    #[arg(value_name = "FILE", help = "A transaction exported with --export-tx.")]
+    pub file: String,
+
+    #[arg(
+        long,
+        value_name = "FILE",
+        help = "Write the signed transaction to FILE instead of overwriting the input."
+    )]
+    pub out: Option<String>,
+}
+
+#[derive(Parser, Debug)]
+pub struct SubmitArgs {
+    #[arg(
+        value_name = "FILE",
+        help = "A transaction exported with --export-tx or --sign-only."
+    )]
+    pub file: String,
+}
@@ -1,25 +1,46 @@
-use anyhow::{Result, bail};
-use std::str::FromStr;
+use anyhow::{Result, anyhow, bail};
+use std::{
+    fs,
+    io::stdout,
+    str::FromStr,
+    time::{Duration, Instant},
+};
 
 use crate::{
     AccountArgs, Miner,
-    args::{AccountCommand, ClaimArgs},
+    args::{AccountCloseArgs, AccountCommand, ClaimArgs, OfflineArgs},
+    constants::MIN_ETH_BALANCE,
+    send::SendOptions,
     utils::{
-        ComputeBudget, TableData, TableSectionTitle, amount_u64_to_f64, ask_confirm,
-        format_timestamp, get_proof, get_proof_with_authority,
+        ComputeBudget, TableData, TableSectionTitle, WalletAccountData, amount_u64_to_f64,
+        ask_confirm, format_duration, format_timestamp, get_multiple_accounts_batched, get_proof,
+        get_proof_with_authority,
     },
 };
+use chrono::Utc;
 use colored::Colorize;
-use eore_api::state::proof_pda;
+use crossterm::{
+    cursor::MoveTo,
+    execute,
+    terminal::{Clear, ClearType},
+};
+use eore_api::state::{Proof, proof_pda};
+use log::warn;
 use solana_program::pubkey::Pubkey;
-use solana_sdk::{native_token::lamports_to_sol, signer::Signer};
+use solana_sdk::{
+    native_token::{lamports_to_sol, sol_to_lamports},
+    program_pack::Pack,
+    signer::Signer,
+};
 use spl_associated_token_account::get_associated_token_address;
 use spl_token::amount_to_ui_amount;
+use steel::AccountDeserialize;
 use tabled::{
     Table,
     settings::{
-        Alignment, Remove, Style,
+        Alignment, Border, Color, Highlight, Remove, Style,
         object::{Columns, Rows},
+        style::BorderColor,
     },
 };
 
@@ -27,7 +48,7 @@ impl Miner {
     pub async fn account(&self, args: AccountArgs) -> Result<()> {
         if let Some(command) = args.command {
             match command {
-                AccountCommand::Close => self.close().await?,
+                AccountCommand::Close(close_args) => self.close(close_args).await?,
             }
         } else {
             self.get_account(args).await?;
@@ -41,32 +62,221 @@ impl Miner {
         if args.proof.is_some() {
             return self.get_proof_account(args).await;
         }
-        let address = if let Some(address) = &args.address {
-            Pubkey::from_str(&address)?
-        } else {
-            signer.pubkey()
-        };
+        let addresses = account_addresses(&args)?;
+        if addresses.len() > 1 || args.file.is_some() {
+            if args.watch.is_some() {
+                bail!("--watch takes a single address");
+            }
+            return self.account_summary(&addresses).await;
+        }
+        let address = addresses.first().copied().unwrap_or(signer.pubkey());
+        if let Some(interval) = args.watch {
+            return self.watch_account(address, interval).await;
+        }
         // Aggregate data
         let mut data = vec![];
-        self.get_account_data(address, &mut data).await;
-        self.get_proof_data(address, &mut data).await;
+        self.get_account_data(address, &mut data, &mut AccountTotals::default())
+            .await?;
+        self.get_proof_data(address, &mut data, &mut AccountTotals::default())
+            .await?;
 
         // Build table
-        let mut table = Table::new(data);
-        table.with(Remove::row(Rows::first()));
-        table.modify(Columns::single(1), Alignment::right());
-        table.with(Style::blank());
-        table.section_title(0, "Account");
-        table.section_title(3, "Proof");
+        println!("{}\n", account_table(data));
+        Ok(())
+    }
 
-        println!("{table}\n");
+    /// Prints one row per wallet and a totals row, fetching the accounts in batches.
+    async fn account_summary(&self, addresses: &[Pubkey]) -> Result<()> {
+        let token_addresses = addresses
+            .iter()
+            .map(|address| get_associated_token_address(address, &eore_api::consts::MINT_ADDRESS))
+            .collect::<Vec<_>>();
+        let proof_addresses = addresses
+            .iter()
+            .map(|address| proof_pda(*address).0)
+            .collect::<Vec<_>>();
+        let wallets = get_multiple_accounts_batched(&self.rpc_client, addresses).await?;
+        let token_accounts =
+            get_multiple_accounts_batched(&self.rpc_client, &token_addresses).await?;
+        let proofs = get_multiple_accounts_batched(&self.rpc_client, &proof_addresses).await?;
+
+        let dash = || "–".to_string();
+        let min_balance = sol_to_lamports(MIN_ETH_BALANCE);
+        let mut rows = Vec::with_capacity(addresses.len() + 1);
+        let mut totals = AccountTotals::default();
+        let mut low_balances = 0;
+        for (((address, wallet), token_account), proof) in addresses
+            .iter()
+            .zip(wallets)
+            .zip(token_accounts)
+            .zip(proofs)
+        {
+            let eth_balance = wallet.map_or(0, |wallet| wallet.lamports);
+            let token_balance = token_account
+                .and_then(|account| spl_token::state::Account::unpack(&account.data).ok())
+                .map_or(0, |account| account.amount);
+            let proof =
+                proof.and_then(|account| Proof::try_from_bytes(&account.data).ok().copied());
+            totals.eth_balance += eth_balance;
+            totals.token_balance += token_balance;
+            if let Some(proof) = proof {
+                totals.proof_balance += proof.balance;
+                totals.total_rewards += proof.total_rewards;
+            }
+            let low_balance = eth_balance < min_balance;
+            if low_balance {
+                low_balances += 1;
+            }
+            let eth = format!("{}", lamports_to_sol(eth_balance));
+            rows.push(WalletAccountData {
+                wallet: address.to_string(),
+                eth: if low_balance {
+                    eth.red().to_string()
+                } else {
+                    eth
+                },
+                balance: amount_u64_to_f64(token_balance).to_string(),
+                proof_balance: proof
+                    .map_or_else(dash, |proof| amount_u64_to_f64(proof.balance).to_string()),
+                last_hash_at: proof.map_or_else(dash, |proof| format_timestamp(proof.last_hash_at)),
+                total_rewards: proof.map_or_else(dash, |proof| {
+                    amount_u64_to_f64(proof.total_rewards).to_string()
+                }),
+                status: match (low_balance, proof.is_some()) {
+                    (true, _) => "Low ETH".bold().red().to_string(),
+                    (false, true) => "OK".green().to_string(),
+                    (false, false) => "No proof".yellow().to_string(),
+                },
+            });
+        }
+        rows.push(WalletAccountData {
+            wallet: format!("Total ({} wallets)", addresses.len())
+                .bold()
+                .to_string(),
+            eth: lamports_to_sol(totals.eth_balance).to_string(),
+            balance: amount_u64_to_f64(totals.token_balance).to_string(),
+            proof_balance: amount_u64_to_f64(totals.proof_balance).to_string(),
+            last_hash_at: dash(),
+            total_rewards: amount_u64_to_f64(totals.total_rewards).to_string(),
+            status: if low_balances > 0 {
+                format!("{} low ETH", low_balances).bold().red().to_string()
+            } else {
+                dash()
+            },
+        });
+
+        let mut table = Table::new(&rows);
+        table.with(Style::blank());
+        table.modify(Columns::new(1..), Alignment::right());
+        table.modify(Rows::first(), Color::BOLD);
+        table.with(Highlight::new(Rows::last()).color(BorderColor::default().top(Color::FG_WHITE)));
+        table.with(Highlight::new(Rows::last()).border(Border::new().top('━')));
+        println!("\n{}\n", table);
+        if low_balances > 0 {
+            println!(
+                "{} {} wallets hold less than {} ETH, the minimum to pay for transactions.\n",
+                "WARNING".bold().yellow(),
+                low_balances,
+                MIN_ETH_BALANCE
+            );
+        }
         Ok(())
     }
-    async fn close(&self) -> Result<()> {
-        let signer = self.signer();
-        let proof = get_proof_with_authority(&self.rpc_client, signer.pubkey())
+
+    /// Refreshes the account table every `interval` seconds, highlighting what
+    /// changed since the previous refresh.
+    async fn watch_account(&self, authority: Pubkey, interval: u64) -> Result<()> {
+        let mut ticks = tokio::time::interval(Duration::from_secs(interval.max(1)));
+        let mut first: Option<(Instant, AccountTotals)> = None;
+        let mut previous: Option<AccountTotals> = None;
+        loop {
+            ticks.tick().await;
+            let mut data = vec![];
+            let mut totals = AccountTotals::default();
+            // Keep the last table up and retry on the next tick
+            let fetched = match self
+                .get_account_data(authority, &mut data, &mut totals)
+                .await
+            {
+                Ok(()) => self.get_proof_data(authority, &mut data, &mut totals).await,
+                Err(err) => Err(err),
+            };
+            if let Err(err) = fetched {
+                warn!(
+                    "Failed to refresh the account: {}. Retrying in {} sec...",
+                    err,
+                    interval.max(1)
+                );
+                continue;
+            }
+            let (started_at, start) = *first.get_or_insert((Instant::now(), totals));
+
+            // Highlight changes since the last refresh
+            if let Some(previous) = previous {
+                let changes = [
+                    (1, totals.token_balance, previous.token_balance, true),
+                    (2, totals.eth_balance, previous.eth_balance, false),
+                    (5, totals.proof_balance, previous.proof_balance, true),
+                    (8, totals.total_hashes, previous.total_hashes, false),
+                    (9, totals.total_rewards, previous.total_rewards, true),
+                ];
+                for (row, current, previous, bitz) in changes {
+                    if let Some(row) = data.get_mut(row)
+                        && current != previous
+                    {
+                        row.value = highlight_change(&row.value, current, previous, bitz);
+                    }
+                }
+            }
+            previous = Some(totals);
+
+            // Reward rate since the watch started
+            let elapsed = started_at.elapsed();
+            let rewards = totals.total_rewards.saturating_sub(start.total_rewards);
+            let hours = elapsed.as_secs_f64() / 3600.0;
+            data.push(TableData {
+                key: "Watching for".to_string(),
+                value: format_duration(elapsed.as_secs() as u32),
+            });
+            data.push(TableData {
+                key: "Rounds".to_string(),
+                value: totals
+                    .total_hashes
+                    .saturating_sub(start.total_hashes)
+                    .to_string(),
+            });
+            data.push(TableData {
+                key: "Rewards".to_string(),
+                value: format!("{} BITZ", amount_u64_to_f64(rewards)),
+            });
+            data.push(TableData {
+                key: "Reward rate".to_string(),
+                value: if hours > 0.0 {
+                    format!("{:.11} BITZ/hour", amount_u64_to_f64(rewards) / hours)
+                } else {
+                    "–".to_string()
+                },
+            });
+
+            let watch_row = data.len() - 4;
+            let mut table = account_table(data);
+            table.section_title(watch_row, "Watch");
+            execute!(stdout(), Clear(ClearType::All), MoveTo(0, 0))?;
+            println!("\n{table}\n");
+            println!(
+                "Updated at {}, refreshing every {} sec. Press Ctrl+C to stop.",
+                format_timestamp(Utc::now().timestamp()),
+                interval.max(1)
+            );
+        }
+    }
+    async fn close(&self, args: AccountCloseArgs) -> Result<()> {
+        let signer = self.signer_pubkey()?;
+        let options = SendOptions::new(self.blockhash_source(args.nonce)?, &args.offline);
+        options.require_rpc("closing reads the proof balance")?;
+        let proof = get_proof_with_authority(&self.rpc_client, signer)
             .await
-            .expect("Failed to fetch proof account");
+            .map_err(|err| anyhow!("Failed to fetch proof account: {}", err))?;
         // Confirm the user wants to close.
         if !ask_confirm(
             format!("{} You have {} BITZ staked in this account.\nAre you sure you want to {}close this account? [Y/n]", 
@@ -78,39 +288,58 @@ impl Miner {
             return Ok(());
         }
         // Claim stake
+        let mut ixs = vec![];
         if proof.balance.gt(&0) {
-            self.claim_from_proof(ClaimArgs {
-                amount: None,
-                to: None,
-                pool_url: None,
-            })
-            .await?;
+            if options.is_offline() || self.dry_run {
+                // Exported or simulated as a single transaction with the close, a
+                // separate claim would never land first
+                let (beneficiary, create_ata_ix) =
+                    self.get_or_initialize_ata(signer, &options).await?;
+                ixs.extend(create_ata_ix);
+                ixs.push(eore_api::sdk::claim(signer, beneficiary, proof.balance));
+            } else {
+                self.claim_from_proof(ClaimArgs {
+                    amount: None,
+                    to: None,
+                    pool_url: None,
+                    nonce: false,
+                    offline: OfflineArgs::default(),
+                })
+                .await?;
+            }
         }
-        let ix = eore_api::sdk::close(signer.pubkey());
-        self.send_and_confirm(&[ix], ComputeBudget::Fixed(500_000), false)
+        ixs.push(eore_api::sdk::close(signer));
+        self.send_and_confirm_with_options(&ixs, ComputeBudget::Dynamic, false, &options)
             .await?;
 
         Ok(())
     }
-    async fn get_account_data(&self, authority: Pubkey, data: &mut Vec<TableData>) {
+    async fn get_account_data(
+        &self,
+        authority: Pubkey,
+        data: &mut Vec<TableData>,
+        totals: &mut AccountTotals,
+    ) -> Result<()> {
         let token_account_address =
             get_associated_token_address(&authority, &eore_api::consts::MINT_ADDRESS);
-        let token_balance = if let Ok(Some(token_account)) = self
+        // A wallet without a token account holds no BITZ
+        totals.token_balance = self
             .rpc_client
-            .get_token_account(&token_account_address)
+            .get_account_with_commitment(&token_account_address, self.rpc_client.commitment())
             .await
-        {
-            token_account.token_amount.ui_amount_string
-        } else {
-            "0".to_string()
-        };
+            .map_err(|err| anyhow!("Failed to fetch token account: {}", err))?
+            .value
+            .and_then(|account| spl_token::state::Account::unpack(&account.data).ok())
+            .map_or(0, |account| account.amount);
+        let token_balance = amount_u64_to_f64(totals.token_balance).to_string();
 
         // Get ETH balance
         let sol_balance = self
             .rpc_client
             .get_balance(&authority)
             .await
-            .expect("Failed to fetch ETH balance");
+            .map_err(|err| anyhow!("Failed to fetch ETH balance: {}", err))?;
+        totals.eth_balance = sol_balance;
         // Aggregate data
         data.push(TableData {
             key: "Address".to_string(),
@@ -124,12 +353,13 @@ impl Miner {
             key: "ETH".to_string(),
             value: format!("{} ETH", lamports_to_sol(sol_balance)),
         });
+        Ok(())
     }
 
     async fn get_proof_account(&self, args: AccountArgs) -> Result<()> {
         // Parse account address
         let proof_address = if let Some(address) = &args.proof {
-            if let Ok(address) = Pubkey::from_str(&address) {
+            if let Ok(address) = Pubkey::from_str(address) {
                 address
             } else {
                 bail!(anyhow::anyhow!("Invalid address: {:?}", address));
@@ -141,33 +371,45 @@ impl Miner {
         // Aggregate data
         let proof = get_proof(&self.rpc_client, proof_address)
             .await
-            .expect("Failed to fetch proof account");
+            .map_err(|err| anyhow!("Failed to fetch proof account: {}", err))?;
+        if let Some(interval) = args.watch {
+            return self.watch_account(proof.authority, interval).await;
+        }
         let mut data = vec![];
-        self.get_account_data(proof.authority, &mut data).await;
-        self.get_proof_data(proof.authority, &mut data).await;
+        self.get_account_data(proof.authority, &mut data, &mut AccountTotals::default())
+            .await?;
+        self.get_proof_data(proof.authority, &mut data, &mut AccountTotals::default())
+            .await?;
 
         // Build table
-        let mut table = Table::new(data);
-        table.with(Remove::row(Rows::first()));
-        table.modify(Columns::single(1), Alignment::right());
-        table.with(Style::blank());
-        table.section_title(0, "Account");
-        table.section_title(3, "Proof");
-
-        println!("{table}\n");
+        println!("{}\n", account_table(data));
         Ok(())
     }
-    async fn get_proof_data(&self, authority: Pubkey, data: &mut Vec<TableData>) {
-        // Parse addresses
+    async fn get_proof_data(
+        &self,
+        authority: Pubkey,
+        data: &mut Vec<TableData>,
+        totals: &mut AccountTotals,
+    ) -> Result<()> {
+        // Parse addresses, a missing proof is shown as not found
         let proof_address = proof_pda(authority).0;
-        let proof = get_proof(&self.rpc_client, proof_address).await;
+        let proof = self
+            .rpc_client
+            .get_account_with_commitment(&proof_address, self.rpc_client.commitment())
+            .await
+            .map_err(|err| anyhow!("Failed to fetch proof account: {}", err))?
+            .value
+            .and_then(|account| Proof::try_from_bytes(&account.data).ok().copied());
 
         // Aggregate data
         data.push(TableData {
             key: "Address".to_string(),
             value: proof_address.to_string(),
         });
-        if let Ok(proof) = proof {
+        if let Some(proof) = proof {
+            totals.proof_balance = proof.balance;
+            totals.total_hashes = proof.total_hashes;
+            totals.total_rewards = proof.total_rewards;
             data.push(TableData {
                 key: "Authority".to_string(),
                 value: authority.to_string(),
@@ -212,5 +454,82 @@ impl Miner {
                 value: "Not found".red().bold().to_string(),
             });
         }
+        Ok(())
+    }
+}
+
+/// The account and proof amounts a watch compares between refreshes.
+#[derive(Clone, Copy, Default)]
+struct AccountTotals {
+    token_balance: u64,
+    eth_balance: u64,
+    proof_balance: u64,
+    total_hashes: u64,
+    total_rewards: u64,
+}
+
+fn account_table(data: Vec<TableData>) -> Table {
+    let mut table = Table::new(data);
+    table.with(Remove::row(Rows::first()));
+    table.modify(Columns::single(1), Alignment::right());
+    table.with(Style::blank());
+    table.section_title(0, "Account");
+    table.section_title(3, "Proof");
+    table
+}
+
+/// Marks a changed table value with its difference from the previous refresh.
+/// `bitz` amounts are in grains, the others in lamports or plain counts.
+fn highlight_change(value: &str, current: u64, previous: u64, bitz: bool) -> String {
+    let (sign, difference) = if current >= previous {
+        ("+", current - previous)
+    } else {
+        ("-", previous - current)
+    };
+    let difference = if bitz {
+        amount_u64_to_f64(difference).to_string()
+    } else if value.ends_with("ETH") {
+        lamports_to_sol(difference).to_string()
+    } else {
+        difference.to_string()
+    };
+    let change = format!("({}{})", sign, difference);
+    let change = if current >= previous {
+        change.green()
+    } else {
+        change.red()
+    };
+    format!("{} {}", value.bold(), change)
+}
+
+/// The addresses given to `account`, from the command line and `--file`, without
+/// duplicates.
+fn account_addresses(args: &AccountArgs) -> Result<Vec<Pubkey>> {
+    let mut values = args.addresses.clone();
+    if let Some(path) = &args.file {
+        let contents =
+            fs::read_to_string(path).map_err(|err| anyhow!("Failed to read {}: {}", path, err))?;
+        values.extend(
+            contents
+                .lines()
+                .map(str::trim)
+                .filter(|line| !line.is_empty() && !line.starts_with('#'))
+                .map(str::to_string),
+        );
+    }
+    let mut addresses = Vec::with_capacity(values.len());
+    for value in values {
+        let address =
+            Pubkey::from_str(&value).map_err(|_| anyhow::anyhow!("Invalid address: {}", value))?;
+        if !addresses.contains(&address) {
+            addresses.push(address);
+        }
+    }
+    if args.file.is_some() && addresses.is_empty() {
+        bail!(
+            "No addresses found in {}",
+            args.file.as_deref().unwrap_or_default()
+        );
     }
+    Ok(addresses)
 }
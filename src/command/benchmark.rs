@@ -1,79 +1,507 @@
-use crate::{Miner, args::BenchmarkArgs, constants::BENCHMARK_TEST_DURATION};
-use anyhow::Result;
-use core_affinity::{get_core_ids, set_for_current};
-use drillx::equix;
+use crate::{
+    Miner,
+    args::BenchmarkArgs,
+    constants::{BENCHMARK_SWEEP_TRIAL_DURATION, BENCHMARK_TEST_DURATION},
+    utils::{
+        BenchmarkCoreData, BenchmarkSweepData, CoreSelection, HashFunction, HashJob, HashPool,
+        select_cores,
+    },
+};
+use anyhow::{Result, bail};
+use colored::Colorize;
+use core_affinity::CoreId;
+use indicatif::ProgressBar;
+use serde::Serialize;
 use solana_rpc_client::spinner;
-use std::{sync::Arc, thread, time::Instant};
+use std::time::Duration;
+use tabled::{
+    Table,
+    settings::{
+        Alignment, Border, Color, Highlight, Style,
+        object::{Columns, Rows},
+        style::BorderColor,
+    },
+};
+use tokio::time::interval;
+
+/// How often the hash count of each core is sampled.
+const SAMPLE_INTERVAL: Duration = Duration::from_secs(1);
+
+/// A run is flagged as throttled when the H/s of its last third is this much lower
+/// than the H/s of its first third, after the warmup.
+const THROTTLE_DROP: f64 = 0.1;
+
+/// The width of the scaling curve bars of a sweep.
+const CURVE_WIDTH: usize = 30;
+
+#[derive(Serialize)]
+struct BenchmarkReport {
+    version: &'static str,
+    duration_secs: u64,
+    warmup_secs: u64,
+    pinned: bool,
+    cores: Vec<usize>,
+    results: Vec<FunctionReport>,
+}
+
+#[derive(Serialize)]
+struct FunctionReport {
+    function: &'static str,
+    elapsed_secs: f64,
+    total: HashStats,
+    cores: Vec<CoreReport>,
+}
+
+#[derive(Serialize)]
+struct CoreReport {
+    core: usize,
+    #[serde(flatten)]
+    stats: HashStats,
+}
+
+/// Hash rates over a run. The warm average, deviation and extremes are taken over
+/// the per second samples after the warmup.
+#[derive(Serialize)]
+struct HashStats {
+    hashes: u64,
+    hashes_per_second: f64,
+    warm_hashes_per_second: f64,
+    std_dev: f64,
+    min: f64,
+    max: f64,
+    throttled: bool,
+}
+
+#[derive(Serialize)]
+struct SweepReport {
+    version: &'static str,
+    trial_secs: u64,
+    warmup_secs: u64,
+    cores: Vec<usize>,
+    trials: Vec<SweepTrial>,
+    recommended: SweepRecommendation,
+}
+
+/// A sweep trial, hashing with `hashes_with_memory` like collecting does.
+#[derive(Serialize)]
+struct SweepTrial {
+    cores: usize,
+    pinned: bool,
+    hashes_per_second: f64,
+    throttled: bool,
+}
+
+#[derive(Serialize)]
+struct SweepRecommendation {
+    cores: usize,
+    pinned: bool,
+    hashes_per_second: f64,
+    /// The collect arguments that reproduce the best trial.
+    args: String,
+}
 
 impl Miner {
     pub async fn benchmark(&self, args: BenchmarkArgs) -> Result<()> {
+        let (default_cores, default_duration) = if args.sweep {
+            (CoreSelection::All, BENCHMARK_SWEEP_TRIAL_DURATION)
+        } else {
+            (CoreSelection::Count(1), BENCHMARK_TEST_DURATION)
+        };
+        let selection = args.cores.clone().unwrap_or(default_cores);
+        let duration = args.duration.unwrap_or(default_duration);
+        if duration <= args.warmup {
+            bail!("--duration must be longer than --warmup");
+        }
+
         // Check num threads
-        let cores = self.parse_cores(args.cores);
-        self.check_num_cores(cores)?;
-
-        // Dispatch job to each thread
-        let challenge = [0; 32];
-        let progress_bar = Arc::new(spinner::new_progress_bar());
-        progress_bar.set_message(format!(
-            "Benchmarking. This will take {} sec...",
-            BENCHMARK_TEST_DURATION
-        ));
-        let core_ids = get_core_ids().expect("Failed to fetch core count");
-        let handles: Vec<_> = core_ids
-            .into_iter()
-            .map(|i| {
-                thread::spawn({
-                    move || {
-                        let timer = Instant::now();
-                        let first_nonce =
-                            u64::MAX.saturating_div(cores).saturating_mul(i.id as u64);
-                        let mut nonce = first_nonce;
-                        let mut memory = equix::SolverMemory::new();
-                        loop {
-                            // Return if core should not be used
-                            if (i.id as u64).ge(&cores) {
-                                return 0;
-                            }
-
-                            // Pin to core, it might not be supported on modern macOS/Apple Silicon
-                            let _ = set_for_current(i);
-
-                            // Create hash
-                            if let Ok(_) = drillx::hash_with_memory(
-                                &mut memory,
-                                &challenge,
-                                &nonce.to_le_bytes(),
-                            ) {
-                                // Increment nonce
-                                nonce += 1;
-                            }
-
-                            // Exit if time has elapsed
-                            if (timer.elapsed().as_secs() as i64).ge(&BENCHMARK_TEST_DURATION) {
-                                break;
-                            }
-                        }
-
-                        // Return hash count
-                        nonce - first_nonce
-                    }
-                })
-            })
-            .collect();
-
-        // Join handles and return best nonce
-        let mut total_nonces = 0;
-        for h in handles {
-            if let Ok(count) = h.join() {
-                total_nonces += count;
+        let core_ids = select_cores(&selection, args.core_options.skip_smt)?;
+        if args.sweep {
+            return sweep(&args, &selection, &core_ids, duration).await;
+        }
+
+        // Hash on the same worker pool collecting uses
+        let hash_pool = HashPool::new(&core_ids, !args.core_options.unpinned);
+        let mut results = Vec::with_capacity(2);
+        for hash_function in [HashFunction::Single, HashFunction::Multiple] {
+            let progress_bar = if args.json {
+                ProgressBar::hidden()
+            } else {
+                spinner::new_progress_bar()
+            };
+            progress_bar.set_message(format!(
+                "Benchmarking {}. This will take {} sec...",
+                hash_function.name(),
+                duration
+            ));
+            let result = benchmark_function(
+                &hash_pool,
+                hash_function,
+                Duration::from_secs(duration),
+                args.warmup as usize,
+            )
+            .await;
+            progress_bar.finish_and_clear();
+            results.push(result?);
+        }
+
+        let report = BenchmarkReport {
+            version: env!("CARGO_PKG_VERSION"),
+            duration_secs: duration,
+            warmup_secs: args.warmup,
+            pinned: !args.core_options.unpinned,
+            cores: core_ids.iter().map(|core| core.id).collect(),
+            results,
+        };
+        if args.json {
+            println!("{}", serde_json::to_string_pretty(&report)?);
+        } else {
+            print_report(&report);
+        }
+        Ok(())
+    }
+}
+
+/// Runs a trial with `hashes_with_memory` for every core count from 1 to the
+/// selected cores, pinned and unpinned, and recommends the fastest.
+async fn sweep(
+    args: &BenchmarkArgs,
+    selection: &CoreSelection,
+    core_ids: &[CoreId],
+    duration: u64,
+) -> Result<()> {
+    let total_trials = core_ids.len() * 2;
+    let mut trials = Vec::with_capacity(total_trials);
+    for cores in 1..=core_ids.len() {
+        for pinned in [true, false] {
+            let progress_bar = if args.json {
+                ProgressBar::hidden()
+            } else {
+                spinner::new_progress_bar()
+            };
+            progress_bar.set_message(format!(
+                "Trial {}/{}: {} cores, {}. This will take {} sec...",
+                trials.len() + 1,
+                total_trials,
+                cores,
+                if pinned { "pinned" } else { "unpinned" },
+                duration
+            ));
+            let hash_pool = HashPool::new(&core_ids[..cores], pinned);
+            let result = benchmark_function(
+                &hash_pool,
+                HashFunction::Multiple,
+                Duration::from_secs(duration),
+                args.warmup as usize,
+            )
+            .await;
+            progress_bar.finish_and_clear();
+            let result = result?;
+            trials.push(SweepTrial {
+                cores,
+                pinned,
+                hashes_per_second: result.total.warm_hashes_per_second,
+                throttled: result.total.throttled,
+            });
+        }
+    }
+
+    // Recommend the same cores, in the form they were selected
+    let best = trials
+        .iter()
+        .max_by(|a, b| a.hashes_per_second.total_cmp(&b.hashes_per_second))
+        .expect("Sweep has no trials");
+    let cores = match selection {
+        CoreSelection::List(_) => {
+            CoreSelection::List(core_ids[..best.cores].iter().map(|core| core.id).collect())
+        }
+        _ => CoreSelection::Count(best.cores),
+    };
+    let mut recommended_args = format!("--cores {}", cores);
+    if args.core_options.skip_smt {
+        recommended_args.push_str(" --skip-smt");
+    }
+    if !best.pinned {
+        recommended_args.push_str(" --unpinned");
+    }
+    let recommended = SweepRecommendation {
+        cores: best.cores,
+        pinned: best.pinned,
+        hashes_per_second: best.hashes_per_second,
+        args: recommended_args,
+    };
+
+    let report = SweepReport {
+        version: env!("CARGO_PKG_VERSION"),
+        trial_secs: duration,
+        warmup_secs: args.warmup,
+        cores: core_ids.iter().map(|core| core.id).collect(),
+        trials,
+        recommended,
+    };
+    if args.json {
+        println!("{}", serde_json::to_string_pretty(&report)?);
+    } else {
+        print_sweep(&report);
+    }
+    Ok(())
+}
+
+/// Hashes on every core of the pool for `duration`, sampling each core's hash
+/// count every second.
+async fn benchmark_function(
+    hash_pool: &HashPool,
+    hash_function: HashFunction,
+    duration: Duration,
+    warmup: usize,
+) -> Result<FunctionReport> {
+    let cores = hash_pool.cores() as u64;
+    let job = HashJob {
+        challenge: [0; 32],
+        hash_function,
+        nonce_indices: (0..cores)
+            .map(|n| u64::MAX.saturating_div(cores).saturating_mul(n))
+            .collect(),
+        cutoff: duration,
+        min_difficulty: 0,
+        target_difficulty: None,
+        min_elapsed: Duration::ZERO,
+        solutions: None,
+    };
+
+    let start = hash_pool.hash_counts();
+    let mut last = start.clone();
+    let mut samples = vec![Vec::new(); start.len()];
+    let mut ticks = interval(SAMPLE_INTERVAL);
+    ticks.tick().await;
+    let round = hash_pool.hash(job, false);
+    tokio::pin!(round);
+    let round = loop {
+        tokio::select! {
+            round = &mut round => break round?,
+            _ = ticks.tick() => {
+                let counts = hash_pool.hash_counts();
+                for ((core_samples, (_, count)), (_, previous)) in
+                    samples.iter_mut().zip(&counts).zip(&last)
+                {
+                    core_samples
+                        .push((count - previous) as f64 / SAMPLE_INTERVAL.as_secs_f64());
+                }
+                last = counts;
             }
         }
+    };
+
+    // The workers have stopped, so their counts are final
+    let end = hash_pool.hash_counts();
+    let mut cores = Vec::with_capacity(end.len());
+    for (((core, count), (_, first)), core_samples) in end.iter().zip(&start).zip(&samples) {
+        cores.push(CoreReport {
+            core: *core,
+            stats: hash_stats(count - first, round.elapsed, core_samples, warmup),
+        });
+    }
+    let total_samples = (0..samples.iter().map(Vec::len).min().unwrap_or_default())
+        .map(|i| samples.iter().map(|core_samples| core_samples[i]).sum())
+        .collect::<Vec<f64>>();
+    let total_hashes = cores.iter().map(|core| core.stats.hashes).sum();
+    Ok(FunctionReport {
+        function: hash_function.name(),
+        elapsed_secs: round.elapsed.as_secs_f64(),
+        total: hash_stats(total_hashes, round.elapsed, &total_samples, warmup),
+        cores,
+    })
+}
+
+fn hash_stats(hashes: u64, elapsed: Duration, samples: &[f64], warmup: usize) -> HashStats {
+    let warm = &samples[warmup.min(samples.len())..];
+    let mean = if warm.is_empty() {
+        0.0
+    } else {
+        warm.iter().sum::<f64>() / warm.len() as f64
+    };
+    let variance = if warm.is_empty() {
+        0.0
+    } else {
+        warm.iter()
+            .map(|sample| (sample - mean).powi(2))
+            .sum::<f64>()
+            / warm.len() as f64
+    };
+    HashStats {
+        hashes,
+        hashes_per_second: hashes as f64 / elapsed.as_secs_f64(),
+        warm_hashes_per_second: mean,
+        std_dev: variance.sqrt(),
+        min: warm.iter().copied().reduce(f64::min).unwrap_or_default(),
+        max: warm.iter().copied().reduce(f64::max).unwrap_or_default(),
+        throttled: is_throttled(warm),
+    }
+}
+
+/// Whether the H/s dropped between the first and last third of the samples, as
+/// it does when the CPU throttles under heat.
+fn is_throttled(samples: &[f64]) -> bool {
+    let third = samples.len() / 3;
+    if third == 0 {
+        return false;
+    }
+    let mean = |samples: &[f64]| samples.iter().sum::<f64>() / samples.len() as f64;
+    let first = mean(&samples[..third]);
+    let last = mean(&samples[samples.len() - third..]);
+    last < first * (1.0 - THROTTLE_DROP)
+}
 
-        // Update log
-        progress_bar.finish_with_message(format!(
-            "Hashpower: {} H/sec",
-            total_nonces.saturating_div(BENCHMARK_TEST_DURATION as u64),
+fn print_report(report: &BenchmarkReport) {
+    println!(
+        "\nBenchmarked {} cores for {} sec per hash function, excluding the first {} sec from warm averages.",
+        report.cores.len(),
+        report.duration_secs,
+        report.warmup_secs
+    );
+    for result in &report.results {
+        let mut rows = result
+            .cores
+            .iter()
+            .map(|core| benchmark_core_row(core.core.to_string(), &core.stats))
+            .collect::<Vec<_>>();
+        rows.push(benchmark_core_row(
+            "Total".bold().to_string(),
+            &result.total,
         ));
-        Ok(())
+        let mut table = Table::new(&rows);
+        table.with(Style::blank());
+        table.modify(Columns::new(1..), Alignment::right());
+        table.modify(Rows::first(), Color::BOLD);
+        table.with(Highlight::new(Rows::last()).color(BorderColor::default().top(Color::FG_WHITE)));
+        table.with(Highlight::new(Rows::last()).border(Border::new().top('━')));
+        println!("\n{}\n{}", result.function.bold(), table);
+        if result.total.throttled {
+            println!("{}", throttling_warning());
+        }
+    }
+
+    // Collecting hashes with hashes_with_memory
+    if let Some(result) = report
+        .results
+        .iter()
+        .find(|result| result.function == HashFunction::Multiple.name())
+    {
+        println!(
+            "\nHashpower: {} H/sec",
+            result.total.warm_hashes_per_second as u64
+        );
+    }
+}
+
+fn benchmark_core_row(core: String, stats: &HashStats) -> BenchmarkCoreData {
+    let std_dev = if stats.warm_hashes_per_second > 0.0 {
+        format!(
+            "{:.0} ({:.1}%)",
+            stats.std_dev,
+            stats.std_dev / stats.warm_hashes_per_second * 100.0
+        )
+    } else {
+        format!("{:.0}", stats.std_dev)
+    };
+    BenchmarkCoreData {
+        core,
+        hashes: stats.hashes.to_string(),
+        rate: format!("{:.0}", stats.hashes_per_second),
+        warm_rate: format!("{:.0}", stats.warm_hashes_per_second),
+        std_dev,
+        min: format!("{:.0}", stats.min),
+        max: format!("{:.0}", stats.max),
+    }
+}
+
+fn print_sweep(report: &SweepReport) {
+    println!(
+        "\nSwept 1 to {} cores for {} sec per trial, excluding the first {} sec from averages.",
+        report.cores.len(),
+        report.trial_secs,
+        report.warmup_secs
+    );
+    let rate = |cores: usize, pinned: bool| {
+        report
+            .trials
+            .iter()
+            .find(|trial| trial.cores == cores && trial.pinned == pinned)
+    };
+    let fastest = report
+        .trials
+        .iter()
+        .map(|trial| trial.hashes_per_second)
+        .fold(0.0, f64::max);
+    let single_core = rate(1, true)
+        .into_iter()
+        .chain(rate(1, false))
+        .map(|trial| trial.hashes_per_second)
+        .fold(0.0, f64::max);
+    let rows = (1..=report.cores.len())
+        .map(|cores| {
+            let pinned = rate(cores, true);
+            let unpinned = rate(cores, false);
+            let best = pinned
+                .into_iter()
+                .chain(unpinned)
+                .map(|trial| trial.hashes_per_second)
+                .fold(0.0, f64::max);
+            let throttled = pinned
+                .into_iter()
+                .chain(unpinned)
+                .any(|trial| trial.throttled);
+            let bar = if fastest > 0.0 {
+                (best / fastest * CURVE_WIDTH as f64).round() as usize
+            } else {
+                0
+            };
+            let format_rate = |trial: Option<&SweepTrial>| {
+                trial.map_or("–".to_string(), |trial| {
+                    format!("{:.0}", trial.hashes_per_second)
+                })
+            };
+            BenchmarkSweepData {
+                cores: if cores == report.recommended.cores {
+                    cores.to_string().bold().to_string()
+                } else {
+                    cores.to_string()
+                },
+                pinned: format_rate(pinned),
+                unpinned: format_rate(unpinned),
+                per_core: format!("{:.0}", best / cores as f64),
+                scaling: if single_core > 0.0 {
+                    format!("{:.0}%", best / (single_core * cores as f64) * 100.0)
+                } else {
+                    "–".to_string()
+                },
+                curve: "█".repeat(bar),
+                throttling: if throttled {
+                    "yes".bold().yellow().to_string()
+                } else {
+                    String::new()
+                },
+            }
+        })
+        .collect::<Vec<_>>();
+    let mut table = Table::new(&rows);
+    table.with(Style::blank());
+    table.modify(Columns::new(1..5), Alignment::right());
+    table.modify(Rows::first(), Color::BOLD);
+    println!("\n{}\n", table);
+    if report.trials.iter().any(|trial| trial.throttled) {
+        println!("{}\n", throttling_warning());
     }
+    println!(
+        "Recommended: {} ({:.0} H/sec)",
+        report.recommended.args.bold(),
+        report.recommended.hashes_per_second
+    );
+}
+
+fn throttling_warning() -> String {
+    format!(
+        "{} H/s dropped by more than {:.0}% during the run. The CPU may be throttling under heat.",
+        "WARNING".bold().yellow(),
+        THROTTLE_DROP * 100.0
+    )
 }
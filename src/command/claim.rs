@@ -3,38 +3,51 @@ use std::str::FromStr;
 use crate::{
     Miner,
     args::ClaimArgs,
-    utils::{ComputeBudget, amount_f64_to_u64, ask_confirm, get_proof_with_authority},
+    pool::Pool,
+    send::SendOptions,
+    utils::{
+        ComputeBudget, amount_f64_to_u64, ask_confirm, get_latest_blockhash_with_retries, get_pool,
+        get_pool_member_onchain, get_proof_with_authority,
+    },
 };
-use anyhow::{Result, bail};
+use anyhow::{Result, anyhow, bail};
 use colored::Colorize;
 use eore_api::consts::MINT_ADDRESS;
-use solana_sdk::signer::Signer;
+use solana_sdk::instruction::Instruction;
 use spl_token::amount_to_ui_amount;
 use steel::Pubkey;
 
 impl Miner {
     pub async fn claim(&self, args: ClaimArgs) -> Result<()> {
-        if args.pool_url.is_some() {
-            bail!("Pool claiming not supported yet.");
+        match args.pool_url.clone() {
+            Some(pool_url) => {
+                let pool = Pool::new(pool_url);
+                self.claim_from_pool(args, &pool).await?;
+            }
+            None => self.claim_from_proof(args).await?,
         }
-        self.claim_from_proof(args).await?;
         Ok(())
     }
 
     pub async fn claim_from_proof(&self, args: ClaimArgs) -> Result<()> {
-        let signer = self.signer();
-        let pubkey = signer.pubkey();
-        let proof = get_proof_with_authority(&self.rpc_client, pubkey)
-            .await
-            .expect("Failed to fetch proof account");
+        let pubkey = self.signer_pubkey()?;
+        let options = SendOptions::new(self.blockhash_source(args.nonce)?, &args.offline);
         let to_wallet = args.to.map_or(pubkey, |ref to| {
             Pubkey::from_str(to).expect("Failed to parse wallet address")
         });
-        let beneficiary = self.get_or_initialize_ata(to_wallet).await;
 
         // Parse amount to claim
-
-        let amount = args.amount.map_or(proof.balance, amount_f64_to_u64);
+        let amount = match args.amount {
+            Some(amount) => amount_f64_to_u64(amount),
+            None => {
+                options.require_rpc("claiming the whole balance reads it, pass --amount")?;
+                get_proof_with_authority(&self.rpc_client, pubkey)
+                    .await
+                    .expect("Failed to fetch proof account")
+                    .balance
+            }
+        };
+        let (beneficiary, create_ata_ix) = self.get_or_initialize_ata(to_wallet, &options).await?;
 
         // Confirm user wants to claim
         if !ask_confirm(
@@ -52,20 +65,146 @@ impl Miner {
         }
 
         // Send and confirm
-        let ixs = vec![eore_api::sdk::claim(pubkey, beneficiary, amount)];
-        self.send_and_confirm(&ixs, ComputeBudget::Fixed(32_000), false)
+        let mut ixs = create_ata_ix.into_iter().collect::<Vec<_>>();
+        ixs.push(eore_api::sdk::claim(pubkey, beneficiary, amount));
+        self.send_and_confirm_with_options(&ixs, ComputeBudget::Dynamic, false, &options)
             .await?;
         Ok(())
     }
 
-    pub async fn get_or_initialize_ata(&self, wallet: Pubkey) -> Pubkey {
+    pub async fn claim_from_pool(&self, args: ClaimArgs, pool: &Pool) -> Result<()> {
+        let pubkey = self.signer_pubkey()?;
+        let options = SendOptions::new(self.blockhash_source(args.nonce)?, &args.offline);
+        options.require_rpc("pool claims read the member balance from the pool")?;
+        let to_wallet = match &args.to {
+            Some(to) => {
+                Pubkey::from_str(to).map_err(|_| anyhow!("Invalid wallet address: {}", to))?
+            }
+            None => pubkey,
+        };
+        let pool_address = pool.get_pool_address().await?.address;
+
+        // Fetch member balances from the pool server and on-chain
+        let db_member = pool.get_pool_member(pubkey).await.map_err(|err| {
+            anyhow!(
+                "Pool {} has no member account for {}. Start collecting with --pool-url to register. ({})",
+                pool.pool_url,
+                pubkey,
+                err
+            )
+        })?;
+        let mut member = get_pool_member_onchain(&self.rpc_client, pubkey, pool_address)
+            .await
+            .map_err(|_| {
+                anyhow!(
+                    "Member account {} not found on-chain for pool {}",
+                    db_member.address,
+                    pool_address
+                )
+            })?;
+
+        // Commit rewards the operator has not attributed on-chain yet
+        let pending_balance =
+            (db_member.total_balance.max(0) as u64).saturating_sub(member.total_balance);
+        if pending_balance.gt(&0) && self.dry_run {
+            println!(
+                "Dry run: skipping the commit of {} BITZ of pending pool rewards",
+                amount_to_ui_amount(pending_balance, eore_api::consts::TOKEN_DECIMALS)
+            );
+        } else if pending_balance.gt(&0)
+            && ask_confirm(
+                format!(
+                    "\nYou have {} of pending pool rewards.\n\nWould you like to commit them before claiming? [Y/n]",
+                    format!(
+                        "{} BITZ",
+                        amount_to_ui_amount(pending_balance, eore_api::consts::TOKEN_DECIMALS)
+                    )
+                    .bold(),
+                )
+                .as_str(),
+            )
+        {
+            let pool_account = get_pool(&self.rpc_client, pool_address).await?;
+            let (hash, _) = get_latest_blockhash_with_retries(&self.rpc_client).await?;
+            let balance_update = pool
+                .post_pool_update_balance(
+                    &self.try_signer()?,
+                    pool_account.authority,
+                    db_member.total_balance as u64,
+                    hash,
+                )
+                .await?;
+            println!("Pool balance updated: {}", balance_update.signature);
+            member = get_pool_member_onchain(&self.rpc_client, pubkey, pool_address).await?;
+        }
+
+        // Parse amount to claim
+        let amount = args.amount.map_or(member.balance, amount_f64_to_u64);
+        if amount.eq(&0) {
+            bail!("No pool rewards to claim.");
+        }
+        if amount.gt(&member.balance) {
+            bail!(
+                "Cannot claim {} BITZ, the member balance is {} BITZ.",
+                amount_to_ui_amount(amount, eore_api::consts::TOKEN_DECIMALS),
+                amount_to_ui_amount(member.balance, eore_api::consts::TOKEN_DECIMALS)
+            );
+        }
+        let (beneficiary, create_ata_ix) = self.get_or_initialize_ata(to_wallet, &options).await?;
+
+        // Confirm user wants to claim
+        if !ask_confirm(
+            format!(
+                "\nYou are about to claim {} from the pool.\n\nAre you sure you want to continue? [Y/n]",
+                format!(
+                    "{} BITZ",
+                    amount_to_ui_amount(amount, eore_api::consts::TOKEN_DECIMALS)
+                )
+                .bold(),
+            )
+            .as_str(),
+        ) {
+            return Ok(());
+        }
+
+        // Send and confirm
+        let mut ixs = create_ata_ix.into_iter().collect::<Vec<_>>();
+        ixs.push(ore_pool_api::sdk::claim(
+            pubkey,
+            beneficiary,
+            pool_address,
+            amount,
+        ));
+        self.send_and_confirm_with_options(&ixs, ComputeBudget::Dynamic, false, &options)
+            .await?;
+        Ok(())
+    }
+
+    /// Returns the BITZ token account of `wallet`, creating it if it does not exist.
+    /// Offline and dry run transactions are not sent, so instead of creating the
+    /// account now this returns an instruction to create it in the same transaction.
+    pub async fn get_or_initialize_ata(
+        &self,
+        wallet: Pubkey,
+        options: &SendOptions,
+    ) -> Result<(Pubkey, Option<Instruction>)> {
         // Initialize client.
-        let signer = self.signer();
+        let signer = self.signer_pubkey()?;
         let client = self.rpc_client.clone();
 
         // Build instructions.
         let token_account_pubkey =
             spl_associated_token_account::get_associated_token_address(&wallet, &MINT_ADDRESS);
+        if options.is_offline() {
+            let ix =
+                spl_associated_token_account::instruction::create_associated_token_account_idempotent(
+                    &signer,
+                    &wallet,
+                    &MINT_ADDRESS,
+                    &spl_token::ID,
+                );
+            return Ok((token_account_pubkey, Some(ix)));
+        }
 
         // Check if ata already exists
         if client
@@ -75,20 +214,23 @@ impl Miner {
             .flatten()
             .is_some()
         {
-            return token_account_pubkey;
+            return Ok((token_account_pubkey, None));
         }
         // Sign and send transaction.
         let ix = spl_associated_token_account::instruction::create_associated_token_account(
-            &signer.pubkey(),
+            &signer,
             &wallet,
             &MINT_ADDRESS,
             &spl_token::ID,
         );
-        self.send_and_confirm(&[ix], ComputeBudget::Fixed(400_000), false)
+        if self.dry_run {
+            return Ok((token_account_pubkey, Some(ix)));
+        }
+        self.send_and_confirm(&[ix], ComputeBudget::Dynamic, false)
             .await
             .ok();
 
         // Return token account address
-        token_account_pubkey
+        Ok((token_account_pubkey, None))
     }
 }
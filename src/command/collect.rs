@@ -2,83 +2,188 @@ use crate::{
     Miner,
     args::CollectArgs,
     constants::MAX_TRANSACTION_POLL_ATTEMPTS,
+    pool::{Pool, pool_nonce_indices},
+    send::{SendAttempt, SendOptions},
     utils::{
-        ComputeBudget, SoloCollectingData, amount_u64_to_f64, find_hash_parallel, format_timestamp,
-        get_clock, get_config, get_updated_proof_with_authority,
+        AccountSubscription, ComputeBudget, HashFunction, HashJob, HashPool, MiningRecord,
+        MiningStatus, PoolCollectingData, SoloCollectingData, WalletCollectingData,
+        amount_u64_to_f64, format_timestamp, select_cores,
     },
 };
 use anyhow::{Result, bail};
-use std::{io::stdout, time::Duration};
+use std::{
+    fs::read_dir,
+    io::stdout,
+    path::Path,
+    sync::{Arc, Mutex, RwLock},
+    time::{Duration, Instant},
+};
 
 use b64::FromBase64;
+use chrono::Utc;
 use colored::Colorize;
+use core_affinity::CoreId;
 use crossterm::{
     cursor::MoveTo,
     execute,
     terminal::{Clear, ClearType},
 };
+use drillx::Solution;
 use eore_api::{
     consts::{BUS_ADDRESSES, EPOCH_DURATION},
     event::MineEvent,
     state::{Bus, Config, proof_pda},
 };
-use log::error;
+use futures::future::join_all;
+use log::{error, warn};
 use solana_program::pubkey::Pubkey;
-use solana_sdk::{signature::Signature, signer::Signer};
+use solana_sdk::{clock::Clock, signature::Signature, signer::Signer};
 use solana_transaction_status::{
     EncodedConfirmedTransactionWithStatusMeta, UiTransactionEncoding,
     option_serializer::OptionSerializer,
 };
 use steel::AccountDeserialize;
 use tabled::{
-    Table,
+    Table, Tabled,
     settings::{
         Alignment, Border, Color, Highlight, Remove, Style,
         object::{Columns, Rows},
         style::BorderColor,
     },
 };
-use tokio::time::sleep;
+use tokio::{sync::mpsc::unbounded_channel, time::sleep};
 
 impl Miner {
-    pub fn parse_cores(&self, cores: String) -> u64 {
-        if cores == "ALL" {
-            num_cpus::get() as u64
-        } else {
-            cores.parse::<u64>().unwrap()
+    pub async fn collect(&self, args: CollectArgs) -> Result<()> {
+        match (args.pool_url.clone(), args.keypairs.clone()) {
+            (Some(_), Some(_)) => bail!("--keypairs is only supported for solo collecting"),
+            (Some(_), None) if args.target_difficulty.is_some() => {
+                bail!("--target-difficulty is only supported for solo collecting")
+            }
+            (Some(pool_url), None) => {
+                let pool = Pool::new(pool_url);
+                self.collect_pool(args, &pool).await
+            }
+            (None, Some(keypairs)) => self.collect_keypairs(args, &keypairs).await,
+            (None, None) => {
+                let core_ids = select_cores(&args.cores, args.core_options.skip_smt)?;
+                let display = SoloDisplay::Rounds {
+                    verbose: args.verbose,
+                };
+                self.collect_solo(args, core_ids, display).await
+            }
         }
     }
-    pub async fn collect(&self, args: CollectArgs) -> Result<()> {
-        match args.pool_url {
-            Some(pool_url) => {
-                error!("Collecting : {}", pool_url);
-                todo!()
+
+    /// Runs a solo collect loop for every keypair of `--keypairs`, splitting the
+    /// cores between them. The fee payer, RPC clients and metrics are shared.
+    async fn collect_keypairs(&self, args: CollectArgs, keypairs: &str) -> Result<()> {
+        let core_ids = select_cores(&args.cores, args.core_options.skip_smt)?;
+        let miners = keypair_paths(keypairs)?
+            .into_iter()
+            .map(|path| Miner {
+                keypair_filepath: Some(path),
+                signer_pubkey: None,
+                solo_collecting_data: Arc::new(RwLock::new(Vec::new())),
+                ..self.clone()
+            })
+            .collect::<Vec<_>>();
+        let mut wallets = Vec::with_capacity(miners.len());
+        for miner in &miners {
+            let pubkey = miner.signer().pubkey();
+            if wallets.iter().any(|(wallet, _)| *wallet == pubkey) {
+                bail!("Keypair {} is listed more than once", pubkey);
             }
-            None => self.collect_solo(args).await,
+            wallets.push((pubkey, miner.solo_collecting_data.clone()));
         }
+        let table = Arc::new(WalletTable {
+            wallets,
+            verbose: args.verbose,
+            print_lock: Mutex::new(()),
+        });
+        table.print()?;
+
+        // Each signer restarts on its own, so one failing wallet does not stop the rest
+        let core_sets = split_cores(core_ids, miners.len());
+        let loops = miners.iter().zip(core_sets).map(|(miner, core_ids)| {
+            let args = args.clone();
+            let display = SoloDisplay::Wallets(table.clone());
+            async move {
+                loop {
+                    let result = miner
+                        .collect_solo(args.clone(), core_ids.clone(), display.clone())
+                        .await;
+                    // A dry run simulates a single round
+                    if miner.dry_run {
+                        if let Err(err) = result {
+                            error!("Error collecting for {}: {}", miner.signer().pubkey(), err);
+                        }
+                        break;
+                    }
+                    if let Err(err) = result {
+                        // The shared fee payer running dry stops every wallet, retrying
+                        // cannot help
+                        if let Err(balance_err) = miner.check_balance().await {
+                            error!(
+                                "Stopped collecting for {}: {}",
+                                miner.signer().pubkey(),
+                                balance_err
+                            );
+                            return Err(balance_err);
+                        }
+                        error!(
+                            "Error collecting for {}: {}. Restarting in 0.5 seconds...",
+                            miner.signer().pubkey(),
+                            err
+                        );
+                        sleep(Duration::from_millis(500)).await;
+                    }
+                }
+                Ok(())
+            }
+        });
+        join_all(loops)
+            .await
+            .into_iter()
+            .find_map(Result::err)
+            .map_or(Ok(()), Err)
     }
-    async fn collect_solo(&self, args: CollectArgs) -> Result<()> {
-        self.open().await?;
-        let core_num_str = args.cores;
-        let cores = self.parse_cores(core_num_str);
-        self.check_num_cores(cores)?;
-        let verbose = args.verbose;
+
+    async fn collect_solo(
+        &self,
+        args: CollectArgs,
+        core_ids: Vec<CoreId>,
+        display: SoloDisplay,
+    ) -> Result<()> {
+        if !self.open().await? {
+            println!("Dry run: collecting cannot be simulated before the proof account exists.");
+            return Ok(());
+        }
+        let hash_pool = HashPool::new(&core_ids, !args.core_options.unpinned);
+        let cores = hash_pool.cores() as u64;
         let signer = self.signer();
         let boost_config_address = eore_boost_api::state::config_pda().0;
+        // Follow the proof, config and clock accounts
+        let mut accounts = AccountSubscription::new(
+            self.rpc_client.clone(),
+            self.websocket_url.clone(),
+            signer.pubkey(),
+        )
+        .await;
         // Start collecting loop
         let mut last_hash_at = 0;
 
         loop {
-            let config = get_config(&self.rpc_client).await?;
+            let proof = accounts.next_proof(last_hash_at).await?;
+            let config = accounts.config().await?;
             let min_difficulty = args.min_difficulty.max(config.min_difficulty as u32);
-            let proof =
-                get_updated_proof_with_authority(&self.rpc_client, signer.pubkey(), last_hash_at)
-                    .await?;
             // Log collecting table
-            self.update_solo_collecting_table(verbose)?;
+            self.show_solo_collecting(&display)?;
             last_hash_at = proof.last_hash_at;
             // Calculate cutoff time
-            let cutoff_time = self.get_cutoff(proof.last_hash_at, args.buffer_time).await;
+            let clock = accounts.clock().await?;
+            let cutoff_time =
+                cutoff_seconds(proof.last_hash_at, clock.unix_timestamp, args.buffer_time);
 
             // Build nonce indices
             let mut nonce_indices = Vec::with_capacity(cores as usize);
@@ -86,24 +191,36 @@ impl Miner {
                 let nonce = u64::MAX.saturating_div(cores).saturating_mul(n);
                 nonce_indices.push(nonce);
             }
-            let solution = find_hash_parallel(
-                proof.challenge,
-                cutoff_time,
-                cores,
-                min_difficulty as u32,
-                nonce_indices.as_slice(),
-                None,
-            )
-            .await?;
+            let job = HashJob {
+                challenge: proof.challenge,
+                hash_function: HashFunction::Multiple,
+                nonce_indices,
+                cutoff: Duration::from_secs(cutoff_time),
+                min_difficulty,
+                // Submitting below the minimum difficulty would fail
+                target_difficulty: args
+                    .target_difficulty
+                    .map(|target| target.max(min_difficulty)),
+                min_elapsed: Duration::from_secs(args.min_elapsed),
+                solutions: None,
+            };
+            let round = hash_pool
+                .hash(job, matches!(display, SoloDisplay::Rounds { .. }))
+                .await?;
+            self.metrics.record_round(
+                signer.pubkey(),
+                &round.core_hashes,
+                round.best_difficulty,
+                round.elapsed,
+            );
+            let solution = round.solution;
 
             // Build instruction set
             let mut ixs = vec![eore_api::sdk::auth(proof_pda(signer.pubkey()).0)];
-            let mut compute_budget = 750_000;
             // Check for reset
-            if self.should_reset(config).await
+            if should_reset(&config, &accounts.clock().await?)
             // && rand::thread_rng().gen_range(0..100).eq(&0)
             {
-                compute_budget += 100_000;
                 ixs.push(eore_api::sdk::reset(signer.pubkey()));
             }
             // Build collect ix
@@ -115,27 +232,46 @@ impl Miner {
                 boost_config_address,
             );
             ixs.push(collect_ix);
-            match self
-                .send_and_confirm(&ixs, ComputeBudget::Fixed(compute_budget), false)
-                .await
-            {
-                Ok(sig) => self.fetch_solo_collect_event(sig, verbose).await?,
+
+            // A dry run simulates one round, there is no transaction to wait for
+            if self.dry_run {
+                self.send_and_confirm(&ixs, ComputeBudget::Dynamic, false)
+                    .await?;
+                println!("Dry run: simulated one round without sending it.");
+                return Ok(());
+            }
+
+            self.metrics.record_submitted();
+            let send_timer = Instant::now();
+            let mut attempt = SendAttempt::default();
+            let result = self
+                .send_and_confirm_tracked(
+                    &ixs,
+                    ComputeBudget::Dynamic,
+                    false,
+                    &SendOptions::default(),
+                    &mut attempt,
+                )
+                .await;
+            self.metrics
+                .record_sent(result.is_ok(), send_timer.elapsed());
+            match result {
+                Ok(sig) => {
+                    self.metrics
+                        .record_priority_fee(attempt.priority_fee, attempt.compute_unit_limit);
+                    self.fetch_solo_collect_event(signer.pubkey(), sig, &display)
+                        .await?
+                }
                 Err(err) => {
-                    let collecting_data = SoloCollectingData::failed();
-                    let mut data = self.solo_collecting_data.write().map_err(|e| {
-                        anyhow::anyhow!(
-                            "failed to write to solo_collecting_data: lock poisoned: {}",
-                            e
-                        )
-                    })?;
-                    if !data.is_empty() {
-                        data.remove(0);
-                    }
-                    data.insert(0, collecting_data);
-                    drop(data);
+                    let record = MiningRecord::failed(
+                        signer.pubkey().to_string(),
+                        attempt.signature.map(|sig| sig.to_string()),
+                        Utc::now().timestamp(),
+                    );
+                    self.record_solo_collect(record, None)?;
 
                     // Log collecting table
-                    self.update_solo_collecting_table(verbose)?;
+                    self.show_solo_collecting(&display)?;
                     println!("{}: {}", "ERROR".bold().red(), err);
 
                     bail!(err);
@@ -143,53 +279,113 @@ impl Miner {
             }
         }
     }
-    async fn open(&self) -> Result<()> {
+    async fn collect_pool(&self, args: CollectArgs, pool: &Pool) -> Result<()> {
+        let signer = self.signer();
+        let authority = signer.pubkey();
+        let member = pool.post_pool_register(authority).await?;
+        if !member.is_approved {
+            warn!(
+                "Pool member {} is not approved by the operator yet",
+                member.address
+            );
+        }
+        let core_ids = select_cores(&args.cores, args.core_options.skip_smt)?;
+        let hash_pool = HashPool::new(&core_ids, !args.core_options.unpinned);
+        let verbose = args.verbose;
+
+        // Stream solutions to the pool server as they are found
+        let (pool_channel, mut solutions) = unbounded_channel::<Solution>();
+        let submitter = {
+            let pool = pool.clone();
+            tokio::spawn(async move {
+                while let Some(solution) = solutions.recv().await {
+                    if let Err(err) = pool.post_pool_solution(authority, &solution).await {
+                        error!("Failed to submit solution to pool: {}", err);
+                    }
+                }
+            })
+        };
+
+        // Start collecting loop
+        let mut last_hash_at = 0;
+        let mut next_challenge = None;
+        let result: Result<()> = async {
+            loop {
+                let member_challenge = match next_challenge.take() {
+                    Some(member_challenge) => member_challenge,
+                    None => {
+                        pool.get_updated_pool_challenge(authority, last_hash_at)
+                            .await?
+                    }
+                };
+                // Log collecting table
+                self.update_pool_collecting_table(verbose)?;
+                last_hash_at = member_challenge.challenge.lash_hash_at;
+                let cutoff_time = cutoff_seconds(
+                    last_hash_at,
+                    member_challenge.unix_timestamp,
+                    args.buffer_time,
+                );
+
+                // Build nonce indices for this member device
+                let nonce_indices = pool_nonce_indices(
+                    member.id as u64,
+                    &member_challenge,
+                    args.device_id,
+                    hash_pool.cores() as u64,
+                )?;
+                let job = HashJob {
+                    challenge: member_challenge.challenge.challenge,
+                    hash_function: HashFunction::Multiple,
+                    nonce_indices,
+                    cutoff: Duration::from_secs(cutoff_time),
+                    min_difficulty: member_challenge.challenge.min_difficulty as u32,
+                    target_difficulty: None,
+                    min_elapsed: Duration::ZERO,
+                    solutions: Some(pool_channel.clone()),
+                };
+
+                // Drop the round if the pool moves on to a new challenge mid-round
+                tokio::select! {
+                    round = hash_pool.hash(job, true) => {
+                        let round = round?;
+                        self.metrics.record_round(
+                            authority,
+                            &round.core_hashes,
+                            round.best_difficulty,
+                            round.elapsed,
+                        );
+                    }
+                    member_challenge = pool.get_updated_pool_challenge(authority, last_hash_at) => {
+                        next_challenge = Some(member_challenge?);
+                    }
+                }
+
+                self.fetch_pool_collect_event(pool, authority, last_hash_at, verbose)
+                    .await?;
+            }
+        }
+        .await;
+        submitter.abort();
+        result
+    }
+
+    /// Opens the proof account if it does not exist yet. Returns whether the proof
+    /// exists, which it does not after a dry run only simulated opening it.
+    async fn open(&self) -> Result<bool> {
         let signer = self.signer();
         let fee_payer = self.fee_payer();
         let proof_address = proof_pda(signer.pubkey()).0;
-        if let Err(_) = self.rpc_client.get_account(&proof_address).await {
+        if self.rpc_client.get_account(&proof_address).await.is_err() {
             let mut ixs = Vec::new();
             let ix = eore_api::sdk::open(signer.pubkey(), signer.pubkey(), fee_payer.pubkey());
             ixs.push(ix);
-            self.send_and_confirm(&ixs, ComputeBudget::Fixed(400_000), false)
+            self.send_and_confirm(&ixs, ComputeBudget::Dynamic, false)
                 .await?;
+            return Ok(!self.dry_run);
         }
 
-        Ok(())
-    }
-
-    async fn get_cutoff(&self, last_hash_at: i64, buffer_time: u64) -> u64 {
-        let clock = get_clock(&self.rpc_client)
-            .await
-            .expect("Failed to fetch clock account");
-        last_hash_at
-            .saturating_add(60)
-            .saturating_sub(buffer_time as i64)
-            .saturating_sub(clock.unix_timestamp)
-            .max(0) as u64
-    }
-
-    async fn should_reset(&self, config: Config) -> bool {
-        let clock = get_clock(&self.rpc_client)
-            .await
-            .expect("Failed to fetch clock account");
-        config
-            .last_reset_at
-            .saturating_add(EPOCH_DURATION)
-            .saturating_sub(5) // Buffer
-            .le(&clock.unix_timestamp)
-    }
-
-    pub fn check_num_cores(&self, core: u64) -> Result<()> {
-        let actual_cores = num_cpus::get() as u64;
-        if core > actual_cores {
-            bail!(
-                "Requested cores {} is greater than actual cores {}",
-                core,
-                actual_cores
-            );
-        }
-        Ok(())
+        Ok(true)
     }
 
     async fn find_bus(&self) -> Pubkey {
@@ -203,12 +399,11 @@ impl Miner {
                     .fold(
                         (BUS_ADDRESSES[0], 0u64),
                         |(max_bus, max_rewards), (idx, account)| {
-                            if let Some(account) = account {
-                                if let Ok(bus) = Bus::try_from_bytes(&account.data) {
-                                    if bus.rewards > max_rewards {
-                                        return (BUS_ADDRESSES[idx], bus.rewards);
-                                    }
-                                }
+                            if let Some(account) = account
+                                && let Ok(bus) = Bus::try_from_bytes(&account.data)
+                                && bus.rewards > max_rewards
+                            {
+                                return (BUS_ADDRESSES[idx], bus.rewards);
                             }
                             (max_bus, max_rewards)
                         },
@@ -218,67 +413,131 @@ impl Miner {
             .unwrap_or(BUS_ADDRESSES[0])
     }
 
+    fn show_solo_collecting(&self, display: &SoloDisplay) -> Result<()> {
+        match display {
+            SoloDisplay::Rounds { verbose } => self.update_solo_collecting_table(*verbose),
+            SoloDisplay::Wallets(table) => table.print(),
+        }
+    }
+
     fn update_solo_collecting_table(&self, verbose: bool) -> Result<()> {
-        execute!(stdout(), Clear(ClearType::All), MoveTo(0, 0))?;
-        let mut rows: Vec<SoloCollectingData> = vec![];
         let data = self.solo_collecting_data.read().map_err(|e| {
             anyhow::anyhow!("failed to read solo_collecting_data: lock poisoned: {}", e)
         })?;
-        rows.extend(data.iter().cloned());
-        let mut table = Table::new(&rows);
-        table.with(Style::blank());
-        table.modify(Columns::new(1..), Alignment::right());
-        table.modify(Rows::first(), Color::BOLD);
-        table.with(
-            Highlight::new(Rows::single(1)).color(BorderColor::default().top(Color::FG_WHITE)),
-        );
-        table.with(Highlight::new(Rows::single(1)).border(Border::new().top('━')));
-        if !verbose {
-            table.with(Remove::column(Columns::new(1..3)));
-        }
-        println!("\n{}\n", table);
-        Ok(())
+        let rows = data
+            .iter()
+            .map(|record| solo_collecting_row(record, verbose))
+            .collect::<Vec<_>>();
+        print_collecting_table(&rows, verbose)
     }
 
-    async fn fetch_solo_collect_event(&self, sig: Signature, verbose: bool) -> Result<()> {
-        let collecting_data = SoloCollectingData::fetching(sig);
-        let mut data = self.solo_collecting_data.write().map_err(|e| {
+    fn update_pool_collecting_table(&self, verbose: bool) -> Result<()> {
+        let data = self.pool_collecting_data.read().map_err(|e| {
+            anyhow::anyhow!("failed to read pool_collecting_data: lock poisoned: {}", e)
+        })?;
+        print_collecting_table(&data, verbose)
+    }
+
+    async fn fetch_pool_collect_event(
+        &self,
+        pool: &Pool,
+        authority: Pubkey,
+        last_hash_at: i64,
+        verbose: bool,
+    ) -> Result<()> {
+        let event = match pool.get_latest_pool_event(authority, last_hash_at).await {
+            Ok(event) => event,
+            Err(err) => {
+                // Keep collecting, the pool may still be submitting this round
+                warn!("{}", err);
+                return Ok(());
+            }
+        };
+        self.metrics.record_reward(event.member_reward);
+        let collecting_data = PoolCollectingData {
+            signature: format_signature(&event.signature.to_string(), verbose),
+            block: event.block.to_string(),
+            timestamp: format_timestamp(event.timestamp as i64),
+            timing: format!("{}s", event.timing),
+            difficulty: event.difficulty.to_string(),
+            base_reward: format_reward(event.net_base_reward),
+            boost_reward: format_reward(event.net_miner_boost_reward),
+            total_reward: format_reward(event.net_reward),
+            my_difficulty: event.member_difficulty.to_string(),
+            my_reward: format_reward(event.member_reward),
+        };
+        let mut data = self.pool_collecting_data.write().map_err(|e| {
             anyhow::anyhow!(
-                "failed to write to solo_collecting_data: lock poisoned: {}",
+                "failed to write to pool_collecting_data: lock poisoned: {}",
                 e
             )
         })?;
         data.insert(0, collecting_data);
-        if !data.is_empty() {
-            data.remove(0);
-        }
-        drop(data);
-        self.update_solo_collecting_table(verbose)?;
-        let tx = self.poll_transaction(sig).await;
-        if let Ok(tx) = tx {
-            let return_data = self.parse_transaction_meta(&tx).await;
-            if let Some(return_data) = return_data {
-                let mut data = self.solo_collecting_data.write().map_err(|e| {
-                    anyhow::anyhow!(
-                        "failed to write to solo_collecting_data: lock poisoned: {}",
-                        e
-                    )
-                })?;
-                let event = MineEvent::from_bytes(&return_data);
-                let collecting_data = SoloCollectingData {
-                    signature: format_signature(&sig, verbose),
-                    block: tx.slot.to_string(),
-                    timestamp: format_timestamp(tx.block_time.unwrap_or_default()),
-                    difficulty: event.difficulty.to_string(),
-                    base_reward: format_reward(event.net_base_reward),
-                    boost_reward: format_reward(event.net_miner_boost_reward),
-                    total_reward: format_reward(event.net_reward),
-                    timing: format!("{}s", event.timing),
-                    status: "Confirmed".bold().green().to_string(),
-                };
+        Ok(())
+    }
 
-                data.insert(0, collecting_data);
+    async fn fetch_solo_collect_event(
+        &self,
+        authority: Pubkey,
+        sig: Signature,
+        display: &SoloDisplay,
+    ) -> Result<()> {
+        let mut record = MiningRecord::sent(
+            authority.to_string(),
+            sig.to_string(),
+            Utc::now().timestamp(),
+        );
+        self.record_solo_collect(record.clone(), None)?;
+        self.show_solo_collecting(display)?;
+        let event = match self.poll_transaction(sig).await {
+            Ok(tx) => match self.parse_transaction_meta(&tx).await {
+                Some(return_data) => Ok((tx, *MineEvent::from_bytes(&return_data))),
+                None => Err(anyhow::anyhow!("No mine event in transaction logs")),
+            },
+            Err(err) => Err(err),
+        };
+        record = match event {
+            Ok((tx, event)) => {
+                self.metrics.record_reward(event.net_reward);
+                MiningRecord {
+                    slot: Some(tx.slot),
+                    timestamp: tx.block_time.unwrap_or(record.timestamp),
+                    difficulty: Some(event.difficulty),
+                    base_reward: event.net_base_reward,
+                    boost_reward: event.net_miner_boost_reward,
+                    total_reward: event.net_reward,
+                    timing: Some(event.timing),
+                    status: MiningStatus::Confirmed,
+                    ..record
+                }
             }
+            Err(err) => {
+                warn!("Failed to fetch mine event of {}: {}", sig, err);
+                MiningRecord {
+                    status: MiningStatus::Unknown,
+                    ..record
+                }
+            }
+        };
+        self.record_solo_collect(record, Some(0))
+    }
+
+    /// Shows the record in the collecting table, replacing the row at `replace`, and
+    /// appends it to the mining history. A later record of the same transaction
+    /// replaces the earlier one when the history is read.
+    fn record_solo_collect(&self, record: MiningRecord, replace: Option<usize>) -> Result<()> {
+        if let Err(err) = self.history().append(&record) {
+            warn!("Failed to write mining history: {}", err);
+        }
+        let mut data = self.solo_collecting_data.write().map_err(|e| {
+            anyhow::anyhow!(
+                "failed to write to solo_collecting_data: lock poisoned: {}",
+                e
+            )
+        })?;
+        match replace {
+            Some(index) if index < data.len() => data[index] = record,
+            _ => data.insert(0, record),
         }
         Ok(())
     }
@@ -294,6 +553,7 @@ impl Miner {
             {
                 Ok(tx) => return Ok(tx),
                 Err(_) => {
+                    self.metrics.record_rpc_error("get_transaction");
                     sleep(Duration::from_secs(1)).await;
                 }
             }
@@ -319,11 +579,220 @@ impl Miner {
     }
 }
 
-fn format_signature(sig: &Signature, verbose: bool) -> String {
-    if verbose {
+fn print_collecting_table<T: Tabled>(rows: &[T], verbose: bool) -> Result<()> {
+    execute!(stdout(), Clear(ClearType::All), MoveTo(0, 0))?;
+    let mut table = Table::new(rows);
+    table.with(Style::blank());
+    table.modify(Columns::new(1..), Alignment::right());
+    table.modify(Rows::first(), Color::BOLD);
+    table.with(Highlight::new(Rows::single(1)).color(BorderColor::default().top(Color::FG_WHITE)));
+    table.with(Highlight::new(Rows::single(1)).border(Border::new().top('━')));
+    if !verbose {
+        table.with(Remove::column(Columns::new(1..3)));
+    }
+    println!("\n{}\n", table);
+    Ok(())
+}
+
+/// Where a solo collect loop shows its rounds.
+#[derive(Clone)]
+enum SoloDisplay {
+    /// Every round of a single signer.
+    Rounds { verbose: bool },
+    /// One row per signer of a `--keypairs` collect.
+    Wallets(Arc<WalletTable>),
+}
+
+/// The rounds of each signer of a `--keypairs` collect.
+struct WalletTable {
+    wallets: Vec<(Pubkey, Arc<RwLock<Vec<MiningRecord>>>)>,
+    verbose: bool,
+    print_lock: Mutex<()>,
+}
+
+impl WalletTable {
+    fn print(&self) -> Result<()> {
+        let _guard = self
+            .print_lock
+            .lock()
+            .unwrap_or_else(|err| err.into_inner());
+        let mut rows = Vec::with_capacity(self.wallets.len() + 1);
+        let (mut rounds, mut failed, mut total_reward) = (0, 0, 0);
+        for (wallet, records) in &self.wallets {
+            let records = records.read().map_err(|e| {
+                anyhow::anyhow!("failed to read solo_collecting_data: lock poisoned: {}", e)
+            })?;
+            let row = wallet_collecting_row(wallet, &records, self.verbose);
+            rounds += count_status(&records, MiningStatus::Confirmed);
+            failed += count_status(&records, MiningStatus::Failed);
+            total_reward += records
+                .iter()
+                .map(|record| record.total_reward)
+                .sum::<u64>();
+            rows.push(row);
+        }
+        let dash = || "–".to_string();
+        rows.push(WalletCollectingData {
+            wallet: "Total".bold().to_string(),
+            rounds: rounds.to_string(),
+            failed: failed.to_string(),
+            difficulty: dash(),
+            timing: dash(),
+            last_reward: dash(),
+            total_reward: format_reward(total_reward),
+            status: dash(),
+        });
+
+        execute!(stdout(), Clear(ClearType::All), MoveTo(0, 0))?;
+        let mut table = Table::new(&rows);
+        table.with(Style::blank());
+        table.modify(Columns::new(1..), Alignment::right());
+        table.modify(Rows::first(), Color::BOLD);
+        table.with(Highlight::new(Rows::last()).color(BorderColor::default().top(Color::FG_WHITE)));
+        table.with(Highlight::new(Rows::last()).border(Border::new().top('━')));
+        println!("\n{}\n", table);
+        Ok(())
+    }
+}
+
+/// The keypair files of `--keypairs`: every `.json` file in a directory, or a comma
+/// separated list of files.
+fn keypair_paths(keypairs: &str) -> Result<Vec<String>> {
+    let dir = Path::new(keypairs);
+    let paths = if dir.is_dir() {
+        let mut paths = read_dir(dir)?
+            .filter_map(|entry| entry.ok())
+            .map(|entry| entry.path())
+            .filter(|path| path.extension().is_some_and(|ext| ext == "json"))
+            .map(|path| path.to_string_lossy().to_string())
+            .collect::<Vec<_>>();
+        paths.sort();
+        paths
+    } else {
+        keypairs
+            .split(',')
+            .map(str::trim)
+            .filter(|path| !path.is_empty())
+            .map(String::from)
+            .collect()
+    };
+    if paths.is_empty() {
+        bail!("No keypairs found in {}", keypairs);
+    }
+    if let Some(missing) = paths.iter().find(|path| !Path::new(path).is_file()) {
+        bail!("Keypair file {} does not exist", missing);
+    }
+    Ok(paths)
+}
+
+/// Splits the cores evenly between `signers`. With more signers than cores, each
+/// signer gets one core and the cores are shared round-robin.
+fn split_cores(core_ids: Vec<CoreId>, signers: usize) -> Vec<Vec<CoreId>> {
+    if signers > core_ids.len() {
+        return (0..signers)
+            .map(|i| vec![core_ids[i % core_ids.len()]])
+            .collect();
+    }
+    let per_signer = core_ids.len() / signers;
+    let extra = core_ids.len() % signers;
+    let mut core_ids = core_ids.into_iter();
+    (0..signers)
+        .map(|i| {
+            core_ids
+                .by_ref()
+                .take(per_signer + usize::from(i < extra))
+                .collect()
+        })
+        .collect()
+}
+
+fn should_reset(config: &Config, clock: &Clock) -> bool {
+    config
+        .last_reset_at
+        .saturating_add(EPOCH_DURATION)
+        .saturating_sub(5) // Buffer
+        .le(&clock.unix_timestamp)
+}
+
+/// Seconds left to hash before submitting, given the on-chain time `now`.
+fn cutoff_seconds(last_hash_at: i64, now: i64, buffer_time: u64) -> u64 {
+    last_hash_at
+        .saturating_add(60)
+        .saturating_sub(buffer_time as i64)
+        .saturating_sub(now)
+        .max(0) as u64
+}
+
+fn solo_collecting_row(record: &MiningRecord, verbose: bool) -> SoloCollectingData {
+    let dash = || "–".to_string();
+    SoloCollectingData {
+        signature: record
+            .signature
+            .as_deref()
+            .map_or_else(dash, |sig| format_signature(sig, verbose)),
+        block: record.slot.map_or_else(dash, |slot| slot.to_string()),
+        timestamp: format_timestamp(record.timestamp),
+        timing: record
+            .timing
+            .map_or_else(dash, |timing| format!("{}s", timing)),
+        difficulty: record
+            .difficulty
+            .map_or_else(dash, |difficulty| difficulty.to_string()),
+        base_reward: format_reward(record.base_reward),
+        boost_reward: format_reward(record.boost_reward),
+        total_reward: format_reward(record.total_reward),
+        status: match record.status {
+            MiningStatus::Sent => "Fetching".to_string(),
+            MiningStatus::Confirmed => "Confirmed".bold().green().to_string(),
+            MiningStatus::Failed => "Failed".bold().red().to_string(),
+            MiningStatus::Unknown => "Unknown".bold().yellow().to_string(),
+        },
+    }
+}
+
+fn wallet_collecting_row(
+    wallet: &Pubkey,
+    records: &[MiningRecord],
+    verbose: bool,
+) -> WalletCollectingData {
+    let dash = || "–".to_string();
+    let last = records
+        .iter()
+        .find(|record| record.status == MiningStatus::Confirmed);
+    WalletCollectingData {
+        wallet: format_signature(&wallet.to_string(), verbose),
+        rounds: count_status(records, MiningStatus::Confirmed).to_string(),
+        failed: count_status(records, MiningStatus::Failed).to_string(),
+        difficulty: last
+            .and_then(|record| record.difficulty)
+            .map_or_else(dash, |difficulty| difficulty.to_string()),
+        timing: last
+            .and_then(|record| record.timing)
+            .map_or_else(dash, |timing| format!("{}s", timing)),
+        last_reward: last.map_or_else(dash, |record| format_reward(record.total_reward)),
+        total_reward: format_reward(records.iter().map(|record| record.total_reward).sum()),
+        status: match records.first().map(|record| record.status) {
+            None => "Collecting".to_string(),
+            Some(MiningStatus::Sent) => "Fetching".to_string(),
+            Some(MiningStatus::Confirmed) => "Confirmed".bold().green().to_string(),
+            Some(MiningStatus::Failed) => "Failed".bold().red().to_string(),
+            Some(MiningStatus::Unknown) => "Unknown".bold().yellow().to_string(),
+        },
+    }
+}
+
+fn count_status(records: &[MiningRecord], status: MiningStatus) -> usize {
+    records
+        .iter()
+        .filter(|record| record.status == status)
+        .count()
+}
+
+fn format_signature(sig: &str, verbose: bool) -> String {
+    if verbose || sig.len() <= 8 {
         sig.to_string()
     } else {
-        format!("{}...", &sig.to_string()[..8])
+        format!("{}...", &sig[..8])
     }
 }
 fn format_reward(reward: u64) -> String {
@@ -0,0 +1,393 @@
+use crate::{
+    Miner,
+    args::EstimateArgs,
+    send::compute_unit_limit,
+    utils::{
+        DifficultyEstimateData, HashFunction, HashJob, HashPool, MiningRecord, MiningStatus,
+        TableData, TableSectionTitle, get_boost_config, get_config, select_cores,
+    },
+};
+use anyhow::{Result, bail};
+use colored::Colorize;
+use drillx::Solution;
+use eore_api::{
+    consts::{BUS_ADDRESSES, ONE_MINUTE, ONE_ORE},
+    state::{Bus, proof_pda},
+};
+use eore_boost_api::consts::DENOMINATOR_BPS;
+use log::{debug, warn};
+use solana_rpc_client::spinner;
+use solana_sdk::{
+    message::Message,
+    native_token::{lamports_to_sol, sol_to_lamports},
+    signature::Signature,
+    signer::Signer,
+};
+use solana_transaction_status::{UiTransactionEncoding, option_serializer::OptionSerializer};
+use std::str::FromStr;
+use std::time::Duration;
+use steel::AccountDeserialize;
+use tabled::{
+    Table,
+    settings::{
+        Alignment, Color, Remove, Style,
+        object::{Columns, Rows},
+    },
+};
+
+/// Scores less likely than this are left out of the distribution table. They still
+/// count towards the expected reward.
+const MIN_LISTED_CHANCE: f64 = 0.0001;
+
+/// How many of the latest confirmed rounds in the mining history the boost share is
+/// averaged over.
+const BOOST_SHARE_ROUNDS: usize = 100;
+
+/// The compute unit limit to price a mine transaction with when the history has no
+/// mine transaction to measure.
+const DEFAULT_COMPUTE_UNIT_LIMIT: u32 = 500_000;
+
+impl Miner {
+    pub async fn estimate(&self, args: EstimateArgs) -> Result<()> {
+        let hashrate = match args.hashrate {
+            Some(hashrate) => hashrate,
+            None => measure_hashrate(&args).await?,
+        };
+        if hashrate <= 0.0 {
+            bail!("The hashrate must be greater than 0");
+        }
+
+        // Fetch on-chain state. Collecting submits to the bus with the most rewards left.
+        let config = get_config(&self.rpc_client).await?;
+        let boost_config = get_boost_config(&self.rpc_client).await;
+        let buses = self
+            .rpc_client
+            .get_multiple_accounts(&BUS_ADDRESSES)
+            .await?
+            .into_iter()
+            .flatten()
+            .filter_map(|account| Bus::try_from_bytes(&account.data).ok().copied())
+            .collect::<Vec<_>>();
+        let bus_rewards = buses
+            .iter()
+            .map(|bus| bus.rewards)
+            .max()
+            .unwrap_or_default();
+        let total_bus_rewards = buses.iter().map(|bus| bus.rewards).sum::<u64>();
+
+        // Distribution of the best score of a round, and its reward
+        let hash_seconds = (ONE_MINUTE as u64).saturating_sub(args.buffer_time);
+        let round_hashes = hashrate * hash_seconds as f64;
+        let min_difficulty = config.min_difficulty as u32;
+        let reward = |difficulty: u32| {
+            2u64.checked_pow(difficulty - min_difficulty)
+                .map_or(u64::MAX, |multiplier| {
+                    config.base_reward_rate.saturating_mul(multiplier)
+                })
+                .min(bus_rewards)
+        };
+        let mut rows = vec![DifficultyEstimateData {
+            difficulty: format!("< {}", min_difficulty),
+            chance: format_chance(1.0 - chance_at_least(round_hashes, min_difficulty)),
+            at_least: format_chance(1.0),
+            reward: "–".to_string(),
+        }];
+        let mut expected_reward = 0.0;
+        for difficulty in min_difficulty.. {
+            let at_least = chance_at_least(round_hashes, difficulty);
+            // Every higher score pays the same once the bus limits the reward
+            if reward(difficulty) == bus_rewards || difficulty == u8::MAX as u32 {
+                expected_reward += at_least * bus_rewards as f64;
+                break;
+            }
+            let chance = at_least - chance_at_least(round_hashes, difficulty + 1);
+            expected_reward += chance * reward(difficulty) as f64;
+            if at_least >= MIN_LISTED_CHANCE {
+                rows.push(DifficultyEstimateData {
+                    difficulty: difficulty.to_string(),
+                    chance: format_chance(chance),
+                    at_least: format_chance(at_least),
+                    reward: format!("{:.11}", reward(difficulty) as f64 / ONE_ORE as f64),
+                });
+            }
+        }
+
+        // Boost rewards come on top of the base reward, in proportion to it. Their
+        // share is measured from the rounds this authority mined.
+        let signer = self.signer();
+        let authority = signer.pubkey().to_string();
+        let history = self.history().read_range(None, None).unwrap_or_else(|err| {
+            warn!("Failed to read the mining history: {}", err);
+            vec![]
+        });
+        let mined = history
+            .iter()
+            .rev()
+            .filter(|record| {
+                record.status == MiningStatus::Confirmed
+                    && record
+                        .authority
+                        .as_ref()
+                        .is_none_or(|record_authority| *record_authority == authority)
+            })
+            .take(BOOST_SHARE_ROUNDS)
+            .collect::<Vec<_>>();
+        let boost_share = boost_share(&mined);
+        let expected_boost = expected_reward * boost_share.unwrap_or_default();
+        let expected_bitz = (expected_reward + expected_boost) / ONE_ORE as f64;
+        let rounds_per_hour = 3600.0 / ONE_MINUTE as f64;
+
+        // Price a mine transaction with the compute unit limit collecting would set
+        let fee_payer = self.fee_payer();
+        let (compute_unit_limit, compute_unit_source) = match args.compute_unit_limit {
+            Some(limit) => (limit, "given"),
+            None => match self.last_mine_compute_units(&mined).await {
+                Some(units) => (
+                    compute_unit_limit(units, self.compute_unit_margin),
+                    "last mine transaction",
+                ),
+                None => (DEFAULT_COMPUTE_UNIT_LIMIT, "default"),
+            },
+        };
+        let ixs = [
+            eore_api::sdk::auth(proof_pda(signer.pubkey()).0),
+            eore_api::sdk::mine(
+                signer.pubkey(),
+                signer.pubkey(),
+                BUS_ADDRESSES[0],
+                Solution::new([0; 16], [0; 8]),
+                eore_boost_api::state::config_pda().0,
+            ),
+        ];
+        let blockhash = self.rpc_client.get_latest_blockhash().await?;
+        let message = Message::new_with_blockhash(&ixs, Some(&fee_payer.pubkey()), &blockhash);
+        let base_fee = self.rpc_client.get_fee_for_message(&message).await?;
+        let priority_fee = if self.dynamic_fee {
+            self.get_priority_fee().await.unwrap_or_else(|err| {
+                warn!("Failed to get dynamic fee: {}", err);
+                self.priority_fee.unwrap_or(0)
+            })
+        } else {
+            self.priority_fee.unwrap_or(0)
+        };
+        let priority_cost = (compute_unit_limit as u64 * priority_fee).div_ceil(1_000_000);
+        let tx_cost = base_fee + priority_cost;
+
+        let mut data = vec![
+            TableData {
+                key: "Hashrate".to_string(),
+                value: format!("{:.0} H/sec", hashrate),
+            },
+            TableData {
+                key: "Hashes per round".to_string(),
+                value: format!("{:.0} in {} sec", round_hashes, hash_seconds),
+            },
+            TableData {
+                key: "Min score".to_string(),
+                value: min_difficulty.to_string(),
+            },
+            TableData {
+                key: "Base reward rate".to_string(),
+                value: format!(
+                    "{:.11} BITZ",
+                    config.base_reward_rate as f64 / ONE_ORE as f64
+                ),
+            },
+            TableData {
+                key: "Bus rewards left".to_string(),
+                value: format!(
+                    "{:.11} BITZ (largest bus {:.11})",
+                    total_bus_rewards as f64 / ONE_ORE as f64,
+                    bus_rewards as f64 / ONE_ORE as f64
+                ),
+            },
+            TableData {
+                key: "Boost take rate".to_string(),
+                value: format!(
+                    "{}%",
+                    boost_config.take_rate as f64 / DENOMINATOR_BPS as f64 * 100.0
+                ),
+            },
+            TableData {
+                key: "Boost share".to_string(),
+                value: match boost_share {
+                    Some(share) => format!(
+                        "{:.1}% of base reward ({} rounds)",
+                        share * 100.0,
+                        mined.len()
+                    ),
+                    None => "– (no mining history)".to_string(),
+                },
+            },
+            TableData {
+                key: "Per round".to_string(),
+                value: format!("{:.11} BITZ", expected_bitz),
+            },
+            TableData {
+                key: "Per hour".to_string(),
+                value: format!("{:.11} BITZ", expected_bitz * rounds_per_hour),
+            },
+            TableData {
+                key: "Per day".to_string(),
+                value: format!("{:.11} BITZ", expected_bitz * rounds_per_hour * 24.0),
+            },
+            TableData {
+                key: "Base fee".to_string(),
+                value: format!("{} ETH", lamports_to_sol(base_fee)),
+            },
+            TableData {
+                key: "Priority fee".to_string(),
+                value: format!(
+                    "{} ETH ({} microlamports x {} CUs, {})",
+                    lamports_to_sol(priority_cost),
+                    priority_fee,
+                    compute_unit_limit,
+                    compute_unit_source
+                ),
+            },
+            TableData {
+                key: "Cost per day".to_string(),
+                value: format!(
+                    "{} ETH",
+                    lamports_to_sol((tx_cost as f64 * rounds_per_hour * 24.0) as u64)
+                ),
+            },
+            TableData {
+                key: "ETH per BITZ".to_string(),
+                value: if expected_bitz > 0.0 {
+                    format!("{:.9}", lamports_to_sol(tx_cost) / expected_bitz)
+                } else {
+                    "–".to_string()
+                },
+            },
+        ];
+        if let Some(price) = args.price {
+            // The priority fee at which a round's reward just pays for its transaction
+            let reward_lamports = sol_to_lamports(expected_bitz * price);
+            let break_even = reward_lamports.saturating_sub(base_fee) as u128 * 1_000_000
+                / compute_unit_limit.max(1) as u128;
+            let profit = (reward_lamports as f64 - tx_cost as f64) * rounds_per_hour * 24.0;
+            data.push(TableData {
+                key: "Break-even priority fee".to_string(),
+                value: if reward_lamports > base_fee {
+                    format!("{} microlamports", break_even)
+                } else {
+                    "Unprofitable at any fee".bold().red().to_string()
+                },
+            });
+            data.push(TableData {
+                key: "Profit per day".to_string(),
+                value: if profit >= 0.0 {
+                    format!("{} ETH", lamports_to_sol(profit as u64))
+                        .green()
+                        .to_string()
+                } else {
+                    format!("-{} ETH", lamports_to_sol(-profit as u64))
+                        .red()
+                        .to_string()
+                },
+            });
+        }
+
+        let mut table = Table::new(data);
+        table.with(Remove::row(Rows::first()));
+        table.modify(Columns::single(1), Alignment::right());
+        table.with(Style::blank());
+        table.section_title(0, "Hashrate");
+        table.section_title(2, "Network");
+        table.section_title(6, "Expected rewards");
+        table.section_title(10, "Fees");
+        println!("{table}\n");
+
+        let mut table = Table::new(rows);
+        table.with(Style::blank());
+        table.modify(Columns::new(1..), Alignment::right());
+        table.modify(Rows::first(), Color::BOLD);
+        println!("{}\n{table}\n", "Best score per round".bold());
+        println!(
+            "Estimates assume one landed transaction per {} sec round.",
+            ONE_MINUTE
+        );
+        Ok(())
+    }
+}
+
+impl Miner {
+    /// The compute units the latest of `mined` that can still be fetched consumed.
+    async fn last_mine_compute_units(&self, mined: &[&MiningRecord]) -> Option<u64> {
+        let signature = mined
+            .iter()
+            .find_map(|record| record.signature.as_deref())
+            .and_then(|signature| Signature::from_str(signature).ok())?;
+        match self
+            .rpc_client
+            .get_transaction(&signature, UiTransactionEncoding::Json)
+            .await
+        {
+            Ok(tx) => match tx.transaction.meta?.compute_units_consumed {
+                OptionSerializer::Some(units) => Some(units),
+                _ => None,
+            },
+            Err(err) => {
+                debug!("Failed to fetch mine transaction {}: {}", signature, err);
+                None
+            }
+        }
+    }
+}
+
+/// The boost reward of mined rounds as a share of their base reward, `None` without
+/// any rounds to measure.
+fn boost_share(mined: &[&MiningRecord]) -> Option<f64> {
+    let base_reward = mined.iter().map(|record| record.base_reward).sum::<u64>();
+    let boost_reward = mined.iter().map(|record| record.boost_reward).sum::<u64>();
+    (base_reward > 0).then(|| boost_reward as f64 / base_reward as f64)
+}
+
+/// Measures the hashrate collecting would get, with the same hash function and cores.
+async fn measure_hashrate(args: &EstimateArgs) -> Result<f64> {
+    let core_ids = select_cores(&args.cores, args.core_options.skip_smt)?;
+    let hash_pool = HashPool::new(&core_ids, !args.core_options.unpinned);
+    let cores = hash_pool.cores() as u64;
+    let progress_bar = spinner::new_progress_bar();
+    progress_bar.set_message(format!(
+        "Measuring hashrate. This will take {} sec...",
+        args.duration
+    ));
+    let job = HashJob {
+        challenge: [0; 32],
+        hash_function: HashFunction::Multiple,
+        nonce_indices: (0..cores)
+            .map(|n| u64::MAX.saturating_div(cores).saturating_mul(n))
+            .collect(),
+        cutoff: Duration::from_secs(args.duration),
+        min_difficulty: 0,
+        target_difficulty: None,
+        min_elapsed: Duration::ZERO,
+        solutions: None,
+    };
+    let round = hash_pool.hash(job, false).await;
+    progress_bar.finish_and_clear();
+    let round = round?;
+    let hashes = round
+        .core_hashes
+        .iter()
+        .map(|(_, hashes)| hashes)
+        .sum::<u64>();
+    Ok(hashes as f64 / round.elapsed.as_secs_f64())
+}
+
+/// The chance that the best of `hashes` hashes scores at least `difficulty`. Each
+/// hash does so with a chance of 2^-difficulty.
+fn chance_at_least(hashes: f64, difficulty: u32) -> f64 {
+    let chance = 0.5f64.powi(difficulty as i32);
+    -(hashes * (-chance).ln_1p()).exp_m1()
+}
+
+fn format_chance(chance: f64) -> String {
+    if chance >= 0.001 {
+        format!("{:.1}%", chance * 100.0)
+    } else {
+        format!("{:.4}%", chance * 100.0)
+    }
+}
@@ -0,0 +1,195 @@
+use std::str::FromStr;
+
+use anyhow::{Result, anyhow};
+use chrono::{Days, Local, NaiveDate, NaiveDateTime, TimeZone};
+use eore_api::consts::ONE_MINUTE;
+use solana_program::pubkey::Pubkey;
+use tabled::{
+    Table,
+    settings::{
+        Alignment, Remove, Style,
+        object::{Columns, Rows},
+    },
+};
+
+use crate::{
+    Miner,
+    args::HistoryArgs,
+    utils::{MiningStatus, TableData, TableSectionTitle, amount_u64_to_f64, format_timestamp},
+};
+
+impl Miner {
+    pub fn history_summary(&self, args: HistoryArgs) -> Result<()> {
+        let from = args
+            .from
+            .as_deref()
+            .map(|date| parse_date(date, false))
+            .transpose()?;
+        let to = args
+            .to
+            .as_deref()
+            .map(|date| parse_date(date, true))
+            .transpose()?;
+        let authority = args
+            .authority
+            .as_deref()
+            .map(|authority| {
+                Pubkey::from_str(authority)
+                    .map_err(|_| anyhow!("Invalid authority address: {}", authority))
+            })
+            .transpose()?;
+        let history = self.history();
+        let mut records = history.read_range(from, to)?;
+        if let Some(authority) = authority {
+            let authority = authority.to_string();
+            records.retain(|record| record.authority.as_ref() == Some(&authority));
+        }
+        let confirmed = records
+            .iter()
+            .filter(|record| record.status == MiningStatus::Confirmed)
+            .collect::<Vec<_>>();
+        let failed = records
+            .iter()
+            .filter(|record| record.status == MiningStatus::Failed)
+            .count();
+        let unconfirmed = records.len() - confirmed.len() - failed;
+
+        let base_reward = confirmed
+            .iter()
+            .map(|record| record.base_reward)
+            .sum::<u64>();
+        let boost_reward = confirmed
+            .iter()
+            .map(|record| record.boost_reward)
+            .sum::<u64>();
+        let total_reward = confirmed
+            .iter()
+            .map(|record| record.total_reward)
+            .sum::<u64>();
+        let difficulties = confirmed
+            .iter()
+            .filter_map(|record| record.difficulty)
+            .collect::<Vec<_>>();
+        let average_difficulty = if difficulties.is_empty() {
+            0.0
+        } else {
+            difficulties.iter().sum::<u64>() as f64 / difficulties.len() as f64
+        };
+        let best_difficulty = difficulties.iter().max().copied().unwrap_or_default();
+
+        // Each transaction closes a one minute round, so the first round started a
+        // minute before the first transaction landed
+        let (first, last) = match (confirmed.first(), confirmed.last()) {
+            (Some(first), Some(last)) => (first.timestamp, last.timestamp),
+            _ => (0, 0),
+        };
+        let hours = if confirmed.is_empty() {
+            0.0
+        } else {
+            (last - first + ONE_MINUTE) as f64 / 3600.0
+        };
+        let reward_per_hour = if hours > 0.0 {
+            amount_u64_to_f64(total_reward) / hours
+        } else {
+            0.0
+        };
+
+        let data = vec![
+            TableData {
+                key: "File".to_string(),
+                value: history.path().display().to_string(),
+            },
+            TableData {
+                key: "From".to_string(),
+                value: from.map_or("–".to_string(), format_timestamp),
+            },
+            TableData {
+                key: "To".to_string(),
+                value: to.map_or("–".to_string(), format_timestamp),
+            },
+            TableData {
+                key: "Authority".to_string(),
+                value: authority.map_or("All".to_string(), |authority| authority.to_string()),
+            },
+            TableData {
+                key: "Transactions".to_string(),
+                value: records.len().to_string(),
+            },
+            TableData {
+                key: "Confirmed".to_string(),
+                value: confirmed.len().to_string(),
+            },
+            TableData {
+                key: "Unconfirmed".to_string(),
+                value: unconfirmed.to_string(),
+            },
+            TableData {
+                key: "Failed".to_string(),
+                value: failed.to_string(),
+            },
+            TableData {
+                key: "Base reward".to_string(),
+                value: format!("{:.11} BITZ", amount_u64_to_f64(base_reward)),
+            },
+            TableData {
+                key: "Boost reward".to_string(),
+                value: format!("{:.11} BITZ", amount_u64_to_f64(boost_reward)),
+            },
+            TableData {
+                key: "Total reward".to_string(),
+                value: format!("{:.11} BITZ", amount_u64_to_f64(total_reward)),
+            },
+            TableData {
+                key: "Average score".to_string(),
+                value: format!("{:.2}", average_difficulty),
+            },
+            TableData {
+                key: "Best score".to_string(),
+                value: best_difficulty.to_string(),
+            },
+            TableData {
+                key: "Hours collecting".to_string(),
+                value: format!("{:.2}", hours),
+            },
+            TableData {
+                key: "Reward per hour".to_string(),
+                value: format!("{:.11} BITZ", reward_per_hour),
+            },
+        ];
+
+        let mut table = Table::new(data);
+        table.with(Remove::row(Rows::first()));
+        table.modify(Columns::single(1), Alignment::right());
+        table.with(Style::blank());
+        table.section_title(0, "History");
+        table.section_title(4, "Transactions");
+        table.section_title(8, "Rewards");
+        println!("{table}\n");
+        Ok(())
+    }
+}
+
+/// Parses a local date or date time into a unix timestamp. A bare date at the end
+/// of a range includes the whole day.
+fn parse_date(date: &str, end_of_range: bool) -> Result<i64> {
+    let datetime = match NaiveDateTime::parse_from_str(date, "%Y-%m-%d %H:%M:%S") {
+        Ok(datetime) => datetime,
+        Err(_) => {
+            let day = NaiveDate::parse_from_str(date, "%Y-%m-%d")
+                .map_err(|_| anyhow!("Invalid date {}, expected YYYY-MM-DD", date))?;
+            let day = if end_of_range {
+                day.checked_add_days(Days::new(1))
+                    .ok_or_else(|| anyhow!("Invalid date {}", date))?
+            } else {
+                day
+            };
+            day.and_hms_opt(0, 0, 0)
+                .ok_or_else(|| anyhow!("Invalid date {}", date))?
+        }
+    };
+    Local
+        .from_local_datetime(&datetime)
+        .earliest()
+        .map(|datetime| datetime.timestamp())
+        .ok_or_else(|| anyhow!("Invalid local time {}", date))
+}
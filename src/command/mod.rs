@@ -2,4 +2,9 @@ mod account;
 mod benchmark;
 mod claim;
 mod collect;
+mod estimate;
+mod history;
+mod nonce;
+mod sign;
 mod stake;
+mod submit;
@@ -0,0 +1,161 @@
+use std::str::FromStr;
+
+use anyhow::{Result, bail};
+use colored::Colorize;
+use solana_program::{nonce, pubkey::Pubkey, system_instruction};
+use solana_sdk::{
+    native_token::{lamports_to_sol, sol_to_lamports},
+    signer::Signer,
+};
+use tabled::{
+    Table,
+    settings::{
+        Alignment, Remove, Style,
+        object::{Columns, Rows},
+    },
+};
+
+use crate::{
+    Miner,
+    args::{NonceArgs, NonceCommand, NonceWithdrawArgs},
+    constants::NONCE_SEED,
+    utils::{
+        BlockhashSource, ComputeBudget, TableData, TableSectionTitle, ask_confirm, get_nonce,
+        nonce_address,
+    },
+};
+
+impl Miner {
+    pub async fn nonce(&self, args: NonceArgs) -> Result<()> {
+        match args.command {
+            Some(NonceCommand::Create) => self.nonce_create().await,
+            Some(NonceCommand::Withdraw(withdraw_args)) => self.nonce_withdraw(withdraw_args).await,
+            Some(NonceCommand::Show) | None => self.nonce_show().await,
+        }
+    }
+
+    /// The blockhash source for commands run with `--nonce`.
+    pub fn blockhash_source(&self, use_nonce: bool) -> Result<BlockhashSource> {
+        if use_nonce {
+            Ok(BlockhashSource::DurableNonce(nonce_address(
+                self.signer_pubkey()?,
+            )))
+        } else {
+            Ok(BlockhashSource::Recent)
+        }
+    }
+
+    async fn nonce_create(&self) -> Result<()> {
+        let signer = self.signer();
+        let address = nonce_address(signer.pubkey());
+        if self.rpc_client.get_account(&address).await.is_ok() {
+            bail!("Nonce account {} already exists", address);
+        }
+        let lamports = self
+            .rpc_client
+            .get_minimum_balance_for_rent_exemption(nonce::State::size())
+            .await?;
+        if !ask_confirm(
+            format!(
+                "\nYou are about to create nonce account {} holding {} ETH for rent.\n\nAre you sure you want to continue? [Y/n]",
+                address.to_string().bold(),
+                lamports_to_sol(lamports),
+            )
+            .as_str(),
+        ) {
+            return Ok(());
+        }
+        let ixs = system_instruction::create_nonce_account_with_seed(
+            &signer.pubkey(),
+            &address,
+            &signer.pubkey(),
+            NONCE_SEED,
+            &signer.pubkey(),
+            lamports,
+        );
+        self.send_and_confirm(&ixs, ComputeBudget::Dynamic, false)
+            .await?;
+        Ok(())
+    }
+
+    async fn nonce_show(&self) -> Result<()> {
+        let address = nonce_address(self.signer().pubkey());
+        let balance = self.rpc_client.get_balance(&address).await?;
+        let nonce = get_nonce(&self.rpc_client, address)
+            .await
+            .map_err(|err| anyhow::anyhow!("{}\nCreate one with `bitz nonce create`.", err))?;
+        let data = vec![
+            TableData {
+                key: "Address".to_string(),
+                value: address.to_string(),
+            },
+            TableData {
+                key: "Authority".to_string(),
+                value: nonce.authority.to_string(),
+            },
+            TableData {
+                key: "Balance".to_string(),
+                value: format!("{} ETH", lamports_to_sol(balance)),
+            },
+            TableData {
+                key: "Nonce".to_string(),
+                value: nonce.blockhash().to_string(),
+            },
+            TableData {
+                key: "Fee per signature".to_string(),
+                value: format!("{} lamports", nonce.get_lamports_per_signature()),
+            },
+        ];
+        let mut table = Table::new(data);
+        table.with(Remove::row(Rows::first()));
+        table.modify(Columns::single(1), Alignment::right());
+        table.with(Style::blank());
+        table.section_title(0, "Nonce");
+        println!("{table}\n");
+        Ok(())
+    }
+
+    async fn nonce_withdraw(&self, args: NonceWithdrawArgs) -> Result<()> {
+        let signer = self.signer();
+        let address = nonce_address(signer.pubkey());
+        let to = match &args.to {
+            Some(to) => Pubkey::from_str(to)?,
+            None => signer.pubkey(),
+        };
+        let balance = self.rpc_client.get_balance(&address).await?;
+        get_nonce(&self.rpc_client, address).await?;
+        let lamports = args.amount.map_or(balance, sol_to_lamports);
+        if lamports == 0 {
+            bail!("There is nothing to withdraw");
+        }
+        if lamports > balance {
+            bail!(
+                "Amount {} ETH exceeds the nonce account balance of {} ETH",
+                lamports_to_sol(lamports),
+                lamports_to_sol(balance)
+            );
+        }
+        let message = if lamports == balance {
+            "This closes the nonce account."
+        } else {
+            "The account must keep enough ETH to stay rent exempt."
+        };
+        if !ask_confirm(
+            format!(
+                "\nYou are about to withdraw {} ETH from nonce account {} to {}. {}\n\nAre you sure you want to continue? [Y/n]",
+                lamports_to_sol(lamports),
+                address,
+                to,
+                message,
+            )
+            .as_str(),
+        ) {
+            return Ok(());
+        }
+        let ix =
+            system_instruction::withdraw_nonce_account(&address, &signer.pubkey(), &to, lamports);
+        self.send_and_confirm(&[ix], ComputeBudget::Dynamic, false)
+            .await?;
+        Ok(())
+    }
+}
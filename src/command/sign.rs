@@ -0,0 +1,51 @@
+use anyhow::{Result, bail};
+use colored::Colorize;
+
+use crate::{
+    Miner,
+    args::SignArgs,
+    send::{ExportedTransaction, missing_signers, sign_partial},
+};
+
+impl Miner {
+    /// Signs an exported transaction with the local keypairs. Makes no RPC calls, so
+    /// it can run on an air-gapped machine between `--export-tx` and `submit`.
+    pub fn sign(&self, args: SignArgs) -> Result<()> {
+        let exported = ExportedTransaction::read(&args.file)?;
+        let mut tx = exported.transaction()?;
+
+        let keypairs = self.local_keypairs();
+        if keypairs.is_empty() {
+            bail!("No keypair found to sign with");
+        }
+        let keypairs = keypairs.iter().collect::<Vec<_>>();
+        let signed = sign_partial(&mut tx, &keypairs)?;
+        if signed == 0 && !tx.is_signed() {
+            bail!(
+                "None of the local keypairs is a missing signer of the transaction. Missing signatures from {}",
+                missing_signers(&tx).join(", ")
+            );
+        }
+
+        // Write the signed transaction back out for `bitz submit`
+        let path = args.out.as_deref().unwrap_or(&args.file);
+        ExportedTransaction::new(&tx, exported.encoding, exported.last_valid_block_height)?
+            .write(path)?;
+        if tx.is_signed() {
+            println!(
+                "{} Transaction {} signed and written to {}",
+                "OK".bold().green(),
+                tx.signatures[0],
+                path
+            );
+        } else {
+            println!(
+                "Added {} signatures and wrote the transaction to {}. Still missing signatures from {}",
+                signed,
+                path,
+                missing_signers(&tx).join(", ")
+            );
+        }
+        Ok(())
+    }
+}
@@ -1,112 +1,497 @@
-#![allow(dead_code)]
 use std::str::FromStr;
 
 use crate::{
     Miner,
-    args::{StakeArgs, StakeCommand, StakeDepositArgs},
-    utils::{ComputeBudget, get_boost},
+    args::{
+        StakeAccountsArgs, StakeArgs, StakeClaimArgs, StakeCommand, StakeDepositArgs,
+        StakeWithdrawArgs,
+    },
+    send::SendOptions,
+    utils::{
+        ComputeBudget, StakeAccountData, StakeData, TableData, TableSectionTitle,
+        amount_f64_to_u64, ask_confirm, format_timestamp, get_boost, get_boost_config,
+        get_boost_proof, get_boost_stake_accounts, get_boosts, get_config, get_mint, get_mints,
+        get_multiple_accounts_batched, get_stake, get_token_symbols,
+    },
+};
+use anyhow::{Result, anyhow, bail};
+use colored::Colorize;
+use eore_api::consts::{MINT_ADDRESS, TOKEN_DECIMALS};
+use eore_api::state::{Config, Proof};
+use eore_boost_api::{
+    consts::DENOMINATOR_BPS,
+    state::{Boost, Config as BoostConfig, Stake, boost_pda, stake_pda},
+};
+use log::{debug, info};
+use solana_sdk::program_pack::Pack;
+use spl_token::{amount_to_ui_amount, state::Mint};
+use steel::{AccountDeserialize, Numeric, Pubkey};
+use tabled::{
+    Table,
+    settings::{
+        Alignment, Color, Remove, Style,
+        object::{Columns, Rows},
+    },
 };
-use anyhow::Result;
-use eore_api::consts::MINT_ADDRESS;
-use eore_boost_api::state::{boost_pda, stake_pda};
-use log::{debug, error, info};
-use solana_sdk::{program_pack::Pack, signer::Signer};
-use spl_token::state::Mint;
-use steel::Pubkey;
 
 impl Miner {
     pub async fn stake(&self, args: StakeArgs) -> Result<()> {
-        if let Some(subcommand) = args.command {
+        if let Some(subcommand) = args.command.clone() {
             match subcommand {
-                StakeCommand::Claim(_args) => todo!(),
-                StakeCommand::Deposit(_args) => todo!(),
-                StakeCommand::Withdraw(_args) => todo!(),
-                StakeCommand::Accounts(_args) => todo!(),
+                StakeCommand::Claim(subargs) => self.stake_claim(subargs, args).await,
+                StakeCommand::Deposit(subargs) => self.stake_deposit(subargs, args).await,
+                StakeCommand::Withdraw(subargs) => self.stake_withdraw(subargs, args).await,
+                StakeCommand::Accounts(subargs) => self.stake_accounts(subargs, args).await,
             }
         } else {
             match args.mint {
-                Some(_mint) => todo!(), //get authority
-                None => todo!(),        //list
+                Some(ref mint) => {
+                    let mint_address = Pubkey::from_str(mint)?;
+                    self.stake_get(mint_address, self.stake_authority(&args)?)
+                        .await
+                }
+                None => self.stake_list(self.stake_authority(&args)?).await,
             }
         }
     }
 
-    async fn stake_deposit(&self, args: StakeDepositArgs, stake_args: StakeArgs) -> Result<()> {
-        // Parse mint address
-        let mint_address = match stake_args.mint {
-            Some(mint_str) => {
-                info!("Using provided mint address: {}", mint_str);
-                Pubkey::from_str(&mint_str).expect("Failed to parse mint address")
+    /// The boost config and its proof, needed to bring boost rewards up to date.
+    async fn get_boost_rewards_pool(&self) -> Result<(BoostConfig, Proof)> {
+        let boost_config = get_boost_config(&self.rpc_client).await;
+        let boost_proof = get_boost_proof(&self.rpc_client).await?;
+        Ok((boost_config, boost_proof))
+    }
+
+    fn stake_authority(&self, args: &StakeArgs) -> Result<Pubkey> {
+        match &args.authority {
+            Some(authority) => Pubkey::from_str(authority)
+                .map_err(|_| anyhow!("Invalid authority address: {}", authority)),
+            None => self.signer_pubkey(),
+        }
+    }
+
+    async fn stake_get(&self, mint_address: Pubkey, authority: Pubkey) -> Result<()> {
+        let boost_address = boost_pda(mint_address).0;
+        let stake_address = stake_pda(authority, boost_address).0;
+        let boost = get_boost(&self.rpc_client, boost_address)
+            .await
+            .map_err(|_| anyhow!("No boost found for mint {}", mint_address))?;
+        let mint = get_mint(&self.rpc_client, mint_address).await?;
+        let (boost_config, boost_proof) = self.get_boost_rewards_pool().await?;
+
+        // Aggregate data
+        let mut data = vec![];
+        data.push(TableData {
+            key: "Address".to_string(),
+            value: boost_address.to_string(),
+        });
+        data.push(TableData {
+            key: "Mint".to_string(),
+            value: mint_address.to_string(),
+        });
+        data.push(TableData {
+            key: "Weight".to_string(),
+            value: boost.weight.to_string(),
+        });
+        data.push(TableData {
+            key: "Deposits".to_string(),
+            value: amount_to_ui_amount(boost.total_deposits, mint.decimals).to_string(),
+        });
+        data.push(TableData {
+            key: "Stakers".to_string(),
+            value: boost.total_stakers.to_string(),
+        });
+        data.push(TableData {
+            key: "Withdraw fee".to_string(),
+            value: format!(
+                "{}%",
+                boost.withdraw_fee as f64 / DENOMINATOR_BPS as f64 * 100.0
+            ),
+        });
+        data.push(TableData {
+            key: "Expires at".to_string(),
+            value: if boost.expires_at.eq(&i64::MAX) {
+                "Never".to_string()
+            } else {
+                format_timestamp(boost.expires_at)
+            },
+        });
+        let stake_section = data.len();
+        data.push(TableData {
+            key: "Address".to_string(),
+            value: stake_address.to_string(),
+        });
+        data.push(TableData {
+            key: "Authority".to_string(),
+            value: authority.to_string(),
+        });
+        if let Ok(stake) = get_stake(&self.rpc_client, stake_address).await {
+            data.push(TableData {
+                key: "Deposits".to_string(),
+                value: amount_to_ui_amount(stake.balance, mint.decimals).to_string(),
+            });
+            data.push(TableData {
+                key: "Rewards".to_string(),
+                value: format!(
+                    "{} BITZ",
+                    amount_to_ui_amount(
+                        pending_rewards(&stake, &boost, &boost_config, &boost_proof),
+                        eore_api::consts::TOKEN_DECIMALS
+                    )
+                ),
+            });
+            data.push(TableData {
+                key: "Last deposit at".to_string(),
+                value: format_timestamp(stake.last_deposit_at),
+            });
+            data.push(TableData {
+                key: "Last withdraw at".to_string(),
+                value: format_timestamp(stake.last_withdraw_at),
+            });
+            data.push(TableData {
+                key: "Last claim at".to_string(),
+                value: format_timestamp(stake.last_claim_at),
+            });
+        } else {
+            data.push(TableData {
+                key: "Status".to_string(),
+                value: "Not found".red().bold().to_string(),
+            });
+        }
+
+        // Build table
+        let mut table = Table::new(data);
+        table.with(Remove::row(Rows::first()));
+        table.modify(Columns::single(1), Alignment::right());
+        table.with(Style::blank());
+        table.section_title(0, "Boost");
+        table.section_title(stake_section, "Stake");
+
+        println!("{table}\n");
+        Ok(())
+    }
+
+    async fn stake_list(&self, authority: Pubkey) -> Result<()> {
+        let config = get_config(&self.rpc_client).await?;
+        let (boost_config, boost_proof) = self.get_boost_rewards_pool().await?;
+        let boosts = get_boosts(&self.rpc_client).await?;
+        let stake_addresses = boosts
+            .iter()
+            .map(|(address, _)| stake_pda(authority, *address).0)
+            .collect::<Vec<_>>();
+        let stake_accounts =
+            get_multiple_accounts_batched(&self.rpc_client, &stake_addresses).await?;
+        let stakes = boosts
+            .iter()
+            .zip(stake_accounts.iter())
+            .filter_map(|((_, boost), account)| {
+                let stake = Stake::try_from_bytes(&account.as_ref()?.data).ok()?;
+                Some((boost, stake))
+            })
+            .collect::<Vec<_>>();
+
+        // Fetch the mints and symbols of every staked boost at once
+        let mints = stakes
+            .iter()
+            .map(|(boost, _)| boost.mint)
+            .collect::<Vec<_>>();
+        let mint_accounts = get_mints(&self.rpc_client, &mints).await?;
+        let symbols = get_token_symbols(&self.rpc_client, &mints).await?;
+
+        // Aggregate data
+        let mut data = vec![];
+        let mut total_rewards = 0u64;
+        for (((boost, stake), mint), symbol) in stakes.into_iter().zip(mint_accounts).zip(symbols) {
+            let decimals = mint.map_or(eore_api::consts::TOKEN_DECIMALS, |mint| mint.decimals);
+            let symbol = symbol.unwrap_or_else(|| format!("{}...", &boost.mint.to_string()[..8]));
+            let rewards = pending_rewards(stake, boost, &boost_config, &boost_proof);
+            total_rewards = total_rewards.saturating_add(rewards);
+            data.push(StakeData {
+                symbol,
+                balance: amount_to_ui_amount(stake.balance, decimals).to_string(),
+                rewards: format!(
+                    "{} BITZ",
+                    amount_to_ui_amount(rewards, eore_api::consts::TOKEN_DECIMALS)
+                ),
+                weight: boost.weight.to_string(),
+                share: if boost.total_deposits > 0 {
+                    format!(
+                        "{:.4}%",
+                        stake.balance as f64 / boost.total_deposits as f64 * 100.0
+                    )
+                } else {
+                    "–".to_string()
+                },
+                apr: estimate_apr(&config, &boost_config, boost)
+                    .map_or("–".to_string(), |apr| format!("{:.2}%", apr)),
+            });
+        }
+        if data.is_empty() {
+            println!("No stake accounts found for {}", authority);
+            return Ok(());
+        }
+        data.push(StakeData {
+            symbol: "Total".bold().to_string(),
+            balance: "".to_string(),
+            rewards: format!(
+                "{} BITZ",
+                amount_to_ui_amount(total_rewards, eore_api::consts::TOKEN_DECIMALS)
+            )
+            .bold()
+            .to_string(),
+            weight: "".to_string(),
+            share: "".to_string(),
+            apr: "".to_string(),
+        });
+
+        // Build table
+        let rows = data.len();
+        let mut table = Table::new(data);
+        table.with(Style::blank());
+        table.modify(Columns::new(1..), Alignment::right());
+        table.modify(Rows::first(), Color::BOLD);
+        table.section_title(0, &format!("Stake {}", authority));
+        table.section_title(rows, "");
+        println!("{table}\n");
+        Ok(())
+    }
+
+    async fn stake_accounts(&self, _args: StakeAccountsArgs, stake_args: StakeArgs) -> Result<()> {
+        let mint_address = parse_mint(&stake_args.mint)?;
+        let boost_address = boost_pda(mint_address).0;
+        let boost = get_boost(&self.rpc_client, boost_address)
+            .await
+            .map_err(|_| anyhow!("No boost found for mint {}", mint_address))?;
+        let mint = get_mint(&self.rpc_client, mint_address).await?;
+        let (boost_config, boost_proof) = self.get_boost_rewards_pool().await?;
+        let mut stake_accounts = get_boost_stake_accounts(&self.rpc_client, boost_address).await?;
+        stake_accounts.sort_by_key(|(_, stake)| std::cmp::Reverse(stake.balance));
+
+        // Aggregate data
+        let data = stake_accounts
+            .iter()
+            .map(|(address, stake)| StakeAccountData {
+                address: address.to_string(),
+                authority: stake.authority.to_string(),
+                balance: amount_to_ui_amount(stake.balance, mint.decimals).to_string(),
+                rewards: format!(
+                    "{} BITZ",
+                    amount_to_ui_amount(
+                        pending_rewards(stake, &boost, &boost_config, &boost_proof),
+                        eore_api::consts::TOKEN_DECIMALS
+                    )
+                ),
+            })
+            .collect::<Vec<_>>();
+
+        // Build table
+        let mut table = Table::new(data);
+        table.with(Style::blank());
+        table.modify(Columns::new(2..), Alignment::right());
+        table.section_title(0, "Stake accounts");
+        println!("{table}\n");
+        Ok(())
+    }
+
+    async fn stake_claim(&self, args: StakeClaimArgs, stake_args: StakeArgs) -> Result<()> {
+        let pubkey = self.signer_pubkey()?;
+        let mint_address = parse_mint(&stake_args.mint)?;
+        let options = SendOptions::new(
+            self.blockhash_source(stake_args.nonce)?,
+            &stake_args.offline,
+        );
+        let to_wallet = match &args.to {
+            Some(to) => {
+                Pubkey::from_str(to).map_err(|_| anyhow!("Invalid wallet address: {}", to))?
             }
-            None => {
-                info!(
-                    "No mint provided, using default MINT_ADDRESS {}",
-                    MINT_ADDRESS
-                );
-                MINT_ADDRESS
+            None => pubkey,
+        };
+
+        // Parse amount to claim, --sign-only cannot read the stake account
+        let amount = match args.amount {
+            Some(amount) if options.sign_only => amount_f64_to_u64(amount),
+            _ => {
+                options.require_rpc("claiming all staking rewards reads them, pass --amount")?;
+                let boost_address = boost_pda(mint_address).0;
+                let stake_address = stake_pda(pubkey, boost_address).0;
+                let boost = get_boost(&self.rpc_client, boost_address)
+                    .await
+                    .map_err(|_| anyhow!("No boost found for mint {}", mint_address))?;
+                let stake = get_stake(&self.rpc_client, stake_address)
+                    .await
+                    .map_err(|_| anyhow!("No stake account found for mint {}", mint_address))?;
+                let (boost_config, boost_proof) = self.get_boost_rewards_pool().await?;
+                args.amount.map_or(
+                    pending_rewards(&stake, &boost, &boost_config, &boost_proof),
+                    amount_f64_to_u64,
+                )
+            }
+        };
+        if amount.eq(&0) {
+            bail!("No staking rewards to claim.");
+        }
+
+        // Confirm user wants to claim
+        if !ask_confirm(
+            format!(
+                "\nYou are about to claim {} of staking rewards.\n\nAre you sure you want to continue? [Y/n]",
+                format!(
+                    "{} BITZ",
+                    amount_to_ui_amount(amount, eore_api::consts::TOKEN_DECIMALS)
+                )
+                .bold(),
+            )
+            .as_str(),
+        ) {
+            return Ok(());
+        }
+
+        // Send and confirm
+        let (beneficiary, create_ata_ix) = self.get_or_initialize_ata(to_wallet, &options).await?;
+        let mut ixs = create_ata_ix.into_iter().collect::<Vec<_>>();
+        ixs.push(eore_boost_api::sdk::claim(
+            pubkey,
+            beneficiary,
+            mint_address,
+            amount,
+        ));
+        self.send_and_confirm_with_options(&ixs, ComputeBudget::Dynamic, false, &options)
+            .await?;
+        Ok(())
+    }
+
+    async fn stake_withdraw(&self, args: StakeWithdrawArgs, stake_args: StakeArgs) -> Result<()> {
+        let pubkey = self.signer_pubkey()?;
+        let mint_address = parse_mint(&stake_args.mint)?;
+        let options = SendOptions::new(
+            self.blockhash_source(stake_args.nonce)?,
+            &stake_args.offline,
+        );
+
+        // The boost program always withdraws to the signer's associated token account
+        let beneficiary =
+            spl_associated_token_account::get_associated_token_address(&pubkey, &mint_address);
+        if let Some(token_account) = &args.token_account
+            && Pubkey::from_str(token_account)?.ne(&beneficiary)
+        {
+            bail!(
+                "Withdrawals can only be sent to the associated token account {}",
+                beneficiary
+            );
+        }
+
+        // Parse amount to withdraw, --sign-only cannot read the stake account or the
+        // decimals of other mints
+        let (decimals, amount) = match args.amount {
+            Some(amount) if options.sign_only && mint_address == MINT_ADDRESS => {
+                (TOKEN_DECIMALS, amount_f64_to_u64(amount))
+            }
+            _ => {
+                options.require_rpc(
+                    "withdrawing reads the stake balance, pass --amount to withdraw BITZ",
+                )?;
+                let mint = get_mint(&self.rpc_client, mint_address).await?;
+                let boost_address = boost_pda(mint_address).0;
+                let stake_address = stake_pda(pubkey, boost_address).0;
+                let stake = get_stake(&self.rpc_client, stake_address)
+                    .await
+                    .map_err(|_| anyhow!("No stake account found for mint {}", mint_address))?;
+                let amount = args.amount.map_or(stake.balance, |amount| {
+                    (amount * 10f64.powf(mint.decimals as f64)) as u64
+                });
+                if amount.gt(&stake.balance) {
+                    bail!(
+                        "Cannot withdraw {}, the stake balance is {}.",
+                        amount_to_ui_amount(amount, mint.decimals),
+                        amount_to_ui_amount(stake.balance, mint.decimals)
+                    );
+                }
+                (mint.decimals, amount)
             }
         };
+        if amount.eq(&0) {
+            bail!("No stake to withdraw.");
+        }
+
+        // Confirm user wants to withdraw
+        if !ask_confirm(
+            format!(
+                "\nYou are about to withdraw {} of mint {}.\n\nAre you sure you want to continue? [Y/n]",
+                amount_to_ui_amount(amount, decimals).to_string().bold(),
+                mint_address,
+            )
+            .as_str(),
+        ) {
+            return Ok(());
+        }
+
+        // Send and confirm
+        let ixs = vec![
+            spl_associated_token_account::instruction::create_associated_token_account_idempotent(
+                &pubkey,
+                &pubkey,
+                &mint_address,
+                &spl_token::ID,
+            ),
+            eore_boost_api::sdk::withdraw(pubkey, mint_address, amount),
+        ];
+        self.send_and_confirm_with_options(&ixs, ComputeBudget::Dynamic, false, &options)
+            .await?;
+        Ok(())
+    }
+
+    async fn stake_deposit(&self, args: StakeDepositArgs, stake_args: StakeArgs) -> Result<()> {
+        // Parse mint address
+        let mint_address = parse_mint(&stake_args.mint)?;
+        info!("Using mint address: {}", mint_address);
         // Get signer
-        let signer = self.signer();
+        let signer = self.signer_pubkey()?;
+        let options = SendOptions::new(
+            self.blockhash_source(stake_args.nonce)?,
+            &stake_args.offline,
+        );
+        options.require_rpc("deposits read the token balance and the stake account")?;
         // Get sender token account
         let sender = match &args.token_account {
             Some(address) => {
                 debug!("Using provided token account: {}", address);
-                Pubkey::from_str(&address).expect("Failed to parse token account address")
+                Pubkey::from_str(address)
+                    .map_err(|_| anyhow!("Invalid token account address: {}", address))?
             }
             None => {
                 let ata = spl_associated_token_account::get_associated_token_address(
-                    &signer.pubkey(),
+                    &signer,
                     &mint_address,
                 );
                 debug!("Using derived ATA address: {}", ata);
                 ata
             }
         };
-        let mint_data = match self.rpc_client.get_account_data(&mint_address).await {
-            Ok(data) => data,
-            Err(err) => {
-                error!("ERROR: Failed to fetch mint account data: {}", err);
-                panic!("Failed to fetch mint account");
-            }
-        };
-        let mint = match Mint::unpack(&mint_data) {
-            Ok(mint) => {
-                println!(
-                    "Successfully unpacked mint data. Decimals: {}",
-                    mint.decimals
-                );
-                mint
-            }
-            Err(err) => {
-                error!("ERROR: Failed to unpack mint data: {}", err);
-                panic!("Failed to parse mint account");
-            }
-        };
-        let token_account = match self.rpc_client.get_token_account(&sender).await {
-            Ok(Some(account)) => {
-                debug!(
-                    "Found token account with balance: {}",
-                    account.token_amount.amount
-                );
-                account
-            }
-            Ok(None) => {
-                error!("ERROR: Token account not found");
-                panic!("Token account not found");
-            }
-            Err(err) => {
-                error!("ERROR: Failed to fetch token account: {}", err);
-                panic!("Failed to fetch token account");
-            }
-        };
+        let mint_data = self
+            .rpc_client
+            .get_account_data(&mint_address)
+            .await
+            .map_err(|err| anyhow!("Failed to fetch mint {}: {}", mint_address, err))?;
+        let mint = Mint::unpack(&mint_data)
+            .map_err(|err| anyhow!("Failed to parse mint {}: {}", mint_address, err))?;
+        debug!("Mint decimals: {}", mint.decimals);
+        let token_account = self
+            .rpc_client
+            .get_token_account(&sender)
+            .await
+            .map_err(|err| anyhow!("Failed to fetch token account {}: {}", sender, err))?
+            .ok_or_else(|| anyhow!("Token account {} not found", sender))?;
+        debug!(
+            "Found token account with balance: {}",
+            token_account.token_amount.amount
+        );
         let amount: u64 = if let Some(amount) = args.amount {
             let calculated = (amount * 10f64.powf(mint.decimals as f64)) as u64;
             debug!("Using provided amount: {} (raw: {})", amount, calculated);
             calculated
         } else {
             let balance = u64::from_str(token_account.token_amount.amount.as_str())
-                .expect("Failed to parse token balance");
+                .map_err(|_| anyhow!("Invalid token balance of {}", sender))?;
             debug!("Using full balance amount: {}", balance);
             balance
         };
@@ -114,19 +499,14 @@ impl Miner {
         let boost_address = boost_pda(mint_address).0;
         debug!("Derived boost PDA: {}", boost_address);
 
-        let stake_address = stake_pda(signer.pubkey(), boost_address).0;
+        let stake_address = stake_pda(signer, boost_address).0;
         debug!("Derived stake PDA: {}", stake_address);
 
-        match get_boost(&self.rpc_client, boost_address).await {
-            Ok(boost) => {
-                info!("Found boost account with weight: {}", boost.weight);
-            }
-            Err(err) => {
-                error!("ERROR: No boost account found for mint {}", mint_address);
-                error!("Error details: {}", err);
-                panic!("Boost account not found");
-            }
-        };
+        let boost = get_boost(&self.rpc_client, boost_address)
+            .await
+            .map_err(|_| anyhow!("No boost found for mint {}", mint_address))?;
+        info!("Found boost account with weight: {}", boost.weight);
+        let mut ixs = vec![];
         if self
             .rpc_client
             .get_account_data(&stake_address)
@@ -134,34 +514,73 @@ impl Miner {
             .is_err()
         {
             info!("Stake account not found, initializing...");
-            let ix = eore_boost_api::sdk::open(signer.pubkey(), signer.pubkey(), mint_address);
-            match self
-                .send_and_confirm(&[ix], ComputeBudget::Fixed(50_000), false)
-                .await
-            {
-                Ok(_) => println!("Successfully initialized stake account"),
-                Err(err) => {
-                    error!("ERROR: Failed to initialize stake account: {}", err);
-                    panic!("Failed to initialize stake account");
-                }
+            let ix = eore_boost_api::sdk::open(signer, signer, mint_address);
+            if options.is_offline() || self.dry_run {
+                // Exported or simulated as a single transaction with the deposit, the
+                // deposit needs the stake account to exist
+                ixs.push(ix);
+            } else {
+                self.send_and_confirm_with_options(&[ix], ComputeBudget::Dynamic, false, &options)
+                    .await
+                    .map_err(|err| anyhow!("Failed to initialize stake account: {}", err))?;
+                println!("Successfully initialized stake account");
             }
         } else {
             debug!("Stake account already exists");
         }
         println!("Sending deposit transaction...");
-        let ix = eore_boost_api::sdk::deposit(signer.pubkey(), mint_address, amount);
-        match self
-            .send_and_confirm(&[ix], ComputeBudget::Fixed(50_000), false)
+        ixs.push(eore_boost_api::sdk::deposit(signer, mint_address, amount));
+        self.send_and_confirm_with_options(&ixs, ComputeBudget::Dynamic, false, &options)
             .await
-        {
-            Ok(_) => {
-                info!("Successfully deposited {} tokens", amount);
-                Ok(())
-            }
-            Err(err) => {
-                error!("ERROR: Failed to deposit tokens: {}", err);
-                panic!("Failed to deposit tokens");
-            }
-        }
+            .map_err(|err| anyhow!("Failed to deposit tokens: {}", err))?;
+        info!("Successfully deposited {} tokens", amount);
+        Ok(())
+    }
+}
+
+fn parse_mint(mint: &Option<String>) -> Result<Pubkey> {
+    match mint {
+        Some(mint) => Pubkey::from_str(mint).map_err(|_| anyhow!("Invalid mint address: {}", mint)),
+        None => Ok(MINT_ADDRESS),
+    }
+}
+
+/// Estimates the claimable rewards of a stake account. Like a claim on-chain, the
+/// boost first collects its share of the rewards waiting in the boost proof, then the
+/// stake account collects its share of the boost rewards since it was last updated.
+fn pending_rewards(
+    stake: &Stake,
+    boost: &Boost,
+    boost_config: &BoostConfig,
+    boost_proof: &Proof,
+) -> u64 {
+    let mut boost = *boost;
+    let mut boost_config = *boost_config;
+    if boost.total_deposits > 0 {
+        boost.collect_rewards(&mut boost_config, boost_proof);
+    }
+    if boost.rewards_factor > stake.last_rewards_factor {
+        let accumulated_rewards = boost.rewards_factor - stake.last_rewards_factor;
+        let personal_rewards = accumulated_rewards * Numeric::from_u64(stake.balance);
+        stake.rewards.saturating_add(personal_rewards.to_u64())
+    } else {
+        stake.rewards
+    }
+}
+
+/// Estimates the yearly staking APR of a boost from the target emissions rate.
+///
+/// Only boosts of the BITZ mint are estimated, since other deposits would need a price.
+fn estimate_apr(config: &Config, boost_config: &BoostConfig, boost: &Boost) -> Option<f64> {
+    if boost.mint.ne(&MINT_ADDRESS)
+        || boost.total_deposits.eq(&0)
+        || boost_config.total_weight.eq(&0)
+    {
+        return None;
     }
+    let yearly_emissions = config.target_emmissions_rate as f64 * 60.0 * 24.0 * 365.0;
+    let staker_emissions =
+        yearly_emissions * boost_config.take_rate as f64 / DENOMINATOR_BPS as f64;
+    let boost_emissions = staker_emissions * boost.weight as f64 / boost_config.total_weight as f64;
+    Some(boost_emissions / boost.total_deposits as f64 * 100.0)
 }
@@ -0,0 +1,48 @@
+use anyhow::Result;
+use colored::Colorize;
+use solana_sdk::signature::Signature;
+
+use crate::{
+    Miner,
+    args::SubmitArgs,
+    send::{ExportedTransaction, sign_missing},
+    utils::ask_confirm,
+};
+
+impl Miner {
+    pub async fn submit(&self, args: SubmitArgs) -> Result<()> {
+        let exported = ExportedTransaction::read(&args.file)?;
+        let mut tx = exported.transaction()?;
+
+        // Fill in the signatures of the local keypairs, a fully signed transaction
+        // needs none
+        let unsigned = tx.signatures.contains(&Signature::default());
+        if unsigned {
+            let keypairs = self.local_keypairs();
+            sign_missing(&mut tx, &keypairs.iter().collect::<Vec<_>>())?;
+        }
+
+        // Confirm user wants to submit
+        if !ask_confirm(
+            format!(
+                "\nYou are about to submit transaction {} with {} instructions{}.\n\nAre you sure you want to continue? [Y/n]",
+                tx.signatures[0].to_string().bold(),
+                tx.message.instructions.len(),
+                if unsigned {
+                    ", signed with your keypair"
+                } else {
+                    ""
+                },
+            )
+            .as_str(),
+        ) {
+            return Ok(());
+        }
+
+        // A durable nonce transaction does not expire
+        let last_valid_block_height = exported.last_valid_block_height.unwrap_or(u64::MAX);
+        self.submit_and_confirm(&tx, last_valid_block_height)
+            .await?;
+        Ok(())
+    }
+}
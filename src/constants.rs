@@ -1,2 +1,8 @@
-pub const BENCHMARK_TEST_DURATION: i64 = 30;
+pub const BENCHMARK_TEST_DURATION: u64 = 30;
+pub const BENCHMARK_WARMUP_DURATION: u64 = 5;
+pub const BENCHMARK_SWEEP_TRIAL_DURATION: u64 = 15;
 pub const MAX_TRANSACTION_POLL_ATTEMPTS: u32 = 30;
+pub const MAX_POOL_EVENT_POLL_ATTEMPTS: u32 = 10;
+/// Below this ETH balance a fee payer cannot send transactions.
+pub const MIN_ETH_BALANCE: f64 = 0.0005;
+pub const NONCE_SEED: &str = "bitz-nonce";
@@ -1,6 +1,8 @@
 mod args;
 mod command;
 mod constants;
+mod metrics;
+mod pool;
 mod send;
 mod utils;
 use std::{
@@ -9,14 +11,24 @@ use std::{
 };
 
 use anyhow::Result;
-use args::{AccountArgs, BenchmarkArgs, ClaimArgs, CollectArgs, StakeArgs};
+use args::{
+    AccountArgs, BenchmarkArgs, ClaimArgs, CollectArgs, EstimateArgs, HistoryArgs, NonceArgs,
+    SignArgs, StakeArgs, SubmitArgs,
+};
 use clap::{Parser, Subcommand};
 use env_logger::Env;
 use log::error;
+use metrics::{Metrics, serve_metrics};
+use send::{
+    FeeStrategy, LocalFeeStrategy, SendEndpoint, fee_strategy_by_name, fee_strategy_for_url,
+};
+use solana_cli_config::ConfigInput;
 use solana_client::nonblocking::rpc_client::RpcClient;
-use solana_sdk::{commitment_config::CommitmentConfig, signature::Keypair};
+use solana_sdk::{
+    commitment_config::CommitmentConfig, pubkey::Pubkey, signature::Keypair, signer::Signer,
+};
 use tokio::time::sleep;
-use utils::{PoolCollectingData, SoloCollectingData};
+use utils::{MiningHistory, MiningRecord, PoolCollectingData};
 
 #[tokio::main]
 async fn main() -> Result<()> {
@@ -36,36 +48,85 @@ async fn main() -> Result<()> {
             solana_cli_config::Config::load(default_config_path)?
         }
     };
+    let (_, websocket_url) = ConfigInput::compute_websocket_url_setting(
+        args.ws_url.as_deref().unwrap_or_default(),
+        &cli_config.websocket_url,
+        args.rpc.as_deref().unwrap_or_default(),
+        &cli_config.json_rpc_url,
+    );
     let cluster_url = args.rpc.unwrap_or(cli_config.json_rpc_url);
     let default_keypair = args.keypair.unwrap_or(cli_config.keypair_path.clone());
+    // The fee payer defaults to the signer, and so does its pubkey
+    let fee_payer_pubkey = match &args.fee_payer {
+        Some(_) => args.fee_payer_pubkey,
+        None => args.fee_payer_pubkey.or(args.signer_pubkey),
+    };
     let fee_payer_filepath = args.fee_payer.unwrap_or(default_keypair.clone());
-    let rpc_client = RpcClient::new_with_commitment(cluster_url, CommitmentConfig::confirmed());
+    let local_fee_strategy = Arc::new(LocalFeeStrategy::new(args.fee_percentile, args.fee_ema));
+    let fee_strategy = match &args.fee_strategy {
+        Some(name) => fee_strategy_by_name(name, local_fee_strategy)?,
+        None => fee_strategy_for_url(
+            args.dynamic_fee_url.as_ref().unwrap_or(&cluster_url),
+            local_fee_strategy,
+        ),
+    };
+    let rpc_client = Arc::new(RpcClient::new_with_commitment(
+        cluster_url.clone(),
+        CommitmentConfig::confirmed(),
+    ));
+    // Transactions are broadcast through the primary RPC and every --send-rpc
+    let mut send_endpoints = vec![SendEndpoint::new(cluster_url, rpc_client.clone())];
+    for url in args.send_rpc {
+        let client = RpcClient::new_with_commitment(url.clone(), CommitmentConfig::confirmed());
+        send_endpoints.push(SendEndpoint::new(url, Arc::new(client)));
+    }
     let solo_collecting_data = Arc::new(RwLock::new(Vec::new()));
     let pool_collecting_data = Arc::new(RwLock::new(Vec::new()));
     let miner = Miner::new(
-        Arc::new(rpc_client),
+        rpc_client,
+        websocket_url,
+        Arc::new(send_endpoints),
         args.priority_fee,
         Some(default_keypair),
         args.dynamic_fee_url,
         args.dynamic_fee,
+        fee_strategy,
+        args.compute_unit_margin,
+        args.dry_run,
         Some(fee_payer_filepath),
+        args.signer_pubkey,
+        fee_payer_pubkey,
+        args.history_file,
         solo_collecting_data,
         pool_collecting_data,
+        Arc::new(Metrics::default()),
     );
 
     match args.command {
         Commands::Benchmark(benchmark_args) => {
             miner.benchmark(benchmark_args).await?;
         }
-        Commands::Collect(collect_args) => loop {
-            match miner.collect(collect_args.clone()).await {
-                Ok(_) => break,
-                Err(e) => {
-                    error!("Error in claim: {}. Restarting in 0.5 seconds...", e);
-                    sleep(Duration::from_millis(500)).await;
+        Commands::Estimate(estimate_args) => {
+            miner.estimate(estimate_args).await?;
+        }
+        Commands::Collect(collect_args) => {
+            if let Some(metrics_addr) = &collect_args.metrics_addr {
+                serve_metrics(metrics_addr, miner.metrics.clone()).await?;
+            }
+            loop {
+                match miner.collect(collect_args.clone()).await {
+                    Ok(_) => break,
+                    // --keypairs restarts each wallet on its own
+                    Err(e) if miner.dry_run || collect_args.keypairs.is_some() => {
+                        return Err(e);
+                    }
+                    Err(e) => {
+                        error!("Error in claim: {}. Restarting in 0.5 seconds...", e);
+                        sleep(Duration::from_millis(500)).await;
+                    }
                 }
             }
-        },
+        }
         Commands::Account(account_args) => {
             miner.account(account_args).await?;
         }
@@ -75,6 +136,18 @@ async fn main() -> Result<()> {
         Commands::Stake(stake_args) => {
             miner.stake(stake_args).await?;
         }
+        Commands::Nonce(nonce_args) => {
+            miner.nonce(nonce_args).await?;
+        }
+        Commands::Sign(sign_args) => {
+            miner.sign(sign_args)?;
+        }
+        Commands::Submit(submit_args) => {
+            miner.submit(submit_args).await?;
+        }
+        Commands::History(history_args) => {
+            miner.history_summary(history_args)?;
+        }
     }
     Ok(())
 }
@@ -86,67 +159,142 @@ struct Miner {
     pub priority_fee: Option<u64>,
     pub dynamic_fee_url: Option<String>,
     pub dynamic_fee: bool,
+    pub fee_strategy: Arc<dyn FeeStrategy>,
+    pub compute_unit_margin: u64,
+    pub dry_run: bool,
     pub rpc_client: Arc<RpcClient>,
+    pub websocket_url: String,
+    pub send_endpoints: Arc<Vec<SendEndpoint>>,
     pub fee_payer_filepath: Option<String>,
-    pub solo_collecting_data: Arc<RwLock<Vec<SoloCollectingData>>>,
+    /// Addresses that stand in for the keypairs when building unsigned transactions.
+    pub signer_pubkey: Option<Pubkey>,
+    pub fee_payer_pubkey: Option<Pubkey>,
+    pub history_filepath: Option<String>,
+    pub solo_collecting_data: Arc<RwLock<Vec<MiningRecord>>>,
     pub pool_collecting_data: Arc<std::sync::RwLock<Vec<PoolCollectingData>>>,
+    pub metrics: Arc<Metrics>,
 }
 impl Miner {
+    #[allow(clippy::too_many_arguments)]
     pub fn new(
         rpc_client: Arc<RpcClient>,
+        websocket_url: String,
+        send_endpoints: Arc<Vec<SendEndpoint>>,
         priority_fee: Option<u64>,
         keypair_filepath: Option<String>,
         dynamic_fee_url: Option<String>,
         dynamic_fee: bool,
+        fee_strategy: Arc<dyn FeeStrategy>,
+        compute_unit_margin: u64,
+        dry_run: bool,
         fee_payer_filepath: Option<String>,
-        solo_collecting_data: Arc<RwLock<Vec<SoloCollectingData>>>,
+        signer_pubkey: Option<Pubkey>,
+        fee_payer_pubkey: Option<Pubkey>,
+        history_filepath: Option<String>,
+        solo_collecting_data: Arc<RwLock<Vec<MiningRecord>>>,
         pool_collecting_data: Arc<RwLock<Vec<PoolCollectingData>>>,
+        metrics: Arc<Metrics>,
     ) -> Self {
         Self {
             rpc_client,
+            websocket_url,
+            send_endpoints,
             keypair_filepath,
             priority_fee,
             dynamic_fee_url,
             dynamic_fee,
+            fee_strategy,
+            compute_unit_margin,
+            dry_run,
             fee_payer_filepath,
+            signer_pubkey,
+            fee_payer_pubkey,
+            history_filepath,
             solo_collecting_data,
             pool_collecting_data,
+            metrics,
         }
     }
 
     pub fn signer(&self) -> Keypair {
-        match self.keypair_filepath.clone() {
-            Some(filepath) => Miner::read_keypair_from_file(filepath.clone()),
-            None => panic!("No keypair provided"),
+        self.try_signer().unwrap_or_else(|err| panic!("{}", err))
+    }
+    pub fn try_signer(&self) -> Result<Keypair> {
+        match &self.keypair_filepath {
+            Some(filepath) => Miner::try_read_keypair_from_file(filepath),
+            None => anyhow::bail!("No keypair provided"),
+        }
+    }
+    /// The signer address, from `--signer-pubkey` if set, so unsigned transactions
+    /// can be built without the keypair.
+    pub fn signer_pubkey(&self) -> Result<Pubkey> {
+        match self.signer_pubkey {
+            Some(pubkey) => Ok(pubkey),
+            None => Ok(self.try_signer()?.pubkey()),
         }
     }
-    pub fn read_keypair_from_file(filepath: String) -> Keypair {
-        use solana_sdk::signature::{Keypair, read_keypair_file};
-        use std::fs::File;
-        use std::io::Read;
+    pub fn try_read_keypair_from_file(filepath: &str) -> Result<Keypair> {
+        use anyhow::{anyhow, bail};
+        use solana_sdk::signature::read_keypair_file;
         use std::path::Path;
 
-        if !Path::new(&filepath).exists() {
-            panic!("File not found at {}", filepath);
+        if !Path::new(filepath).exists() {
+            bail!("File not found at {}", filepath);
         }
 
-        match read_keypair_file(&filepath) {
-            Ok(keypair) => keypair,
+        match read_keypair_file(filepath) {
+            Ok(keypair) => Ok(keypair),
             Err(_) => {
                 // Try to read as base58 string
-                let mut file = File::open(&filepath).expect("Failed to open file");
-                let mut contents = String::new();
-                file.read_to_string(&mut contents)
-                    .expect("Failed to read file contents");
-                let trimmed_contents = contents.trim();
-                Keypair::from_base58_string(trimmed_contents)
+                let contents = std::fs::read_to_string(filepath)
+                    .map_err(|err| anyhow!("Failed to read {}: {}", filepath, err))?;
+                let bytes = bs58::decode(contents.trim())
+                    .into_vec()
+                    .map_err(|_| anyhow!("Invalid keypair file {}", filepath))?;
+                Keypair::from_bytes(&bytes)
+                    .map_err(|_| anyhow!("Invalid keypair file {}", filepath))
             }
         }
     }
+    /// The signer and fee payer keypairs that can be read, skipping missing files, so
+    /// commands that only may need a signature do not require them.
+    pub fn local_keypairs(&self) -> Vec<Keypair> {
+        let mut keypairs: Vec<Keypair> = vec![];
+        for filepath in [&self.keypair_filepath, &self.fee_payer_filepath]
+            .into_iter()
+            .flatten()
+        {
+            match Miner::try_read_keypair_from_file(filepath) {
+                Ok(keypair) => {
+                    if !keypairs.iter().any(|k| k.pubkey() == keypair.pubkey()) {
+                        keypairs.push(keypair);
+                    }
+                }
+                Err(err) => log::debug!("Skipping keypair: {}", err),
+            }
+        }
+        keypairs
+    }
     pub fn fee_payer(&self) -> Keypair {
-        match self.fee_payer_filepath.clone() {
-            Some(filepath) => Miner::read_keypair_from_file(filepath.clone()),
-            None => panic!("No fee payer keypair provided"),
+        self.try_fee_payer().unwrap_or_else(|err| panic!("{}", err))
+    }
+    pub fn try_fee_payer(&self) -> Result<Keypair> {
+        match &self.fee_payer_filepath {
+            Some(filepath) => Miner::try_read_keypair_from_file(filepath),
+            None => anyhow::bail!("No fee payer keypair provided"),
+        }
+    }
+    /// The fee payer address, from `--fee-payer-pubkey` if set.
+    pub fn fee_payer_pubkey(&self) -> Result<Pubkey> {
+        match self.fee_payer_pubkey {
+            Some(pubkey) => Ok(pubkey),
+            None => Ok(self.try_fee_payer()?.pubkey()),
+        }
+    }
+    pub fn history(&self) -> MiningHistory {
+        match self.history_filepath.clone() {
+            Some(filepath) => MiningHistory::new(filepath),
+            None => MiningHistory::new(MiningHistory::default_path()),
         }
     }
 }
@@ -161,6 +309,20 @@ struct Args {
         global = true
     )]
     rpc: Option<String>,
+    #[arg(
+        long,
+        value_name = "WEBSOCKET_URL",
+        help = "PubSub websocket of your RPC provider. Derived from the RPC url when not provided.",
+        global = true
+    )]
+    ws_url: Option<String>,
+    #[arg(
+        long,
+        value_name = "NETWORK_URL",
+        help = "Additional RPC to broadcast transactions through. Can be repeated.",
+        global = true
+    )]
+    send_rpc: Vec<String>,
     #[clap(
         global = true,
         short = 'C',
@@ -184,6 +346,20 @@ struct Args {
         global = true
     )]
     fee_payer: Option<String>,
+    #[arg(
+        long,
+        value_name = "PUBKEY",
+        help = "Signer address to build unsigned transactions for with --export-tx, instead of reading the keypair.",
+        global = true
+    )]
+    signer_pubkey: Option<Pubkey>,
+    #[arg(
+        long,
+        value_name = "PUBKEY",
+        help = "Fee payer address to build unsigned transactions for with --export-tx. Defaults to --signer-pubkey.",
+        global = true
+    )]
+    fee_payer_pubkey: Option<Pubkey>,
     #[arg(
         long,
         value_name = "MICROLAMPORTS",
@@ -201,6 +377,51 @@ struct Args {
     dynamic_fee_url: Option<String>,
     #[arg(long, help = "Enable dynamic priority fees", global = true)]
     dynamic_fee: bool,
+    #[arg(
+        long,
+        value_name = "STRATEGY",
+        help = "Priority fee estimator: helius, alchemy, quiknode, triton or local. Guessed from the fee RPC host when not provided.",
+        global = true
+    )]
+    fee_strategy: Option<String>,
+    #[arg(
+        long,
+        value_name = "PERCENTILE",
+        help = "Percentile of recent fees the local fee strategy pays.",
+        default_value = "75",
+        value_parser = clap::value_parser!(u8).range(0..=100),
+        global = true
+    )]
+    fee_percentile: u8,
+    #[arg(
+        long,
+        value_name = "ALPHA",
+        help = "Smooth local fee estimates with an exponential moving average, weighting each new estimate by ALPHA (0 to 1).",
+        value_parser = parse_ema_alpha,
+        global = true
+    )]
+    fee_ema: Option<f64>,
+    #[arg(
+        long,
+        value_name = "PERCENT",
+        help = "Safety margin added to the simulated compute units of a transaction.",
+        default_value = "20",
+        global = true
+    )]
+    compute_unit_margin: u64,
+    #[arg(
+        long,
+        help = "Simulate transactions and print them instead of sending them.",
+        global = true
+    )]
+    dry_run: bool,
+    #[arg(
+        long,
+        value_name = "HISTORY_FILEPATH",
+        help = "Filepath to the mining history log. Defaults to ~/.config/bitz/history.jsonl.",
+        global = true
+    )]
+    history_file: Option<String>,
 
     #[command(subcommand)]
     command: Commands,
@@ -213,8 +434,26 @@ enum Commands {
     Collect(CollectArgs),
     #[command(about = "Benchmark your machine's hashpower")]
     Benchmark(BenchmarkArgs),
+    #[command(about = "Estimate your expected rewards and fees for a hashrate")]
+    Estimate(EstimateArgs),
     #[command(about = "Claim your collecting yield")]
     Claim(ClaimArgs),
     #[command(about = "Manage your stake positions")]
     Stake(StakeArgs),
+    #[command(about = "Manage your durable nonce account")]
+    Nonce(NonceArgs),
+    #[command(about = "Sign a transaction exported with --export-tx, without any RPC calls")]
+    Sign(SignArgs),
+    #[command(about = "Send a transaction exported with --export-tx or --sign-only")]
+    Submit(SubmitArgs),
+    #[command(about = "Summarize your mining history")]
+    History(HistoryArgs),
+}
+
+fn parse_ema_alpha(value: &str) -> Result<f64, String> {
+    let alpha = value.parse::<f64>().map_err(|err| err.to_string())?;
+    if alpha <= 0.0 || alpha > 1.0 {
+        return Err(format!("{} is not in the range (0, 1]", alpha));
+    }
+    Ok(alpha)
 }
@@ -0,0 +1,4 @@
+mod registry;
+mod server;
+pub use registry::*;
+pub use server::*;
@@ -0,0 +1,283 @@
+use std::{
+    collections::BTreeMap,
+    fmt::Write,
+    sync::{
+        Mutex,
+        atomic::{AtomicU64, Ordering},
+    },
+    time::Duration,
+};
+
+use solana_program::pubkey::Pubkey;
+
+use crate::utils::amount_u64_to_f64;
+
+/// Counters and gauges of the collect loop, rendered in the Prometheus text format.
+#[derive(Default)]
+pub struct Metrics {
+    core_hashes: Mutex<BTreeMap<usize, CoreHashes>>,
+    round_best_difficulty: Mutex<BTreeMap<Pubkey, u64>>,
+    rounds: AtomicU64,
+    transactions_submitted: AtomicU64,
+    transactions_landed: AtomicU64,
+    transactions_failed: AtomicU64,
+    rewards_earned: AtomicU64,
+    priority_fee: AtomicU64,
+    priority_fee_paid: AtomicU64,
+    rpc_errors: Mutex<BTreeMap<&'static str, u64>>,
+    hashing_micros: AtomicU64,
+    sending_micros: AtomicU64,
+}
+
+#[derive(Default)]
+struct CoreHashes {
+    total: u64,
+    rate: f64,
+}
+
+impl Metrics {
+    /// Records a finished hashing round of `authority`. `core_hashes` holds the core
+    /// id and the number of hashes it computed during `elapsed`.
+    pub fn record_round(
+        &self,
+        authority: Pubkey,
+        core_hashes: &[(usize, u64)],
+        best_difficulty: u32,
+        elapsed: Duration,
+    ) {
+        let seconds = elapsed.as_secs_f64();
+        let mut cores = self
+            .core_hashes
+            .lock()
+            .unwrap_or_else(|err| err.into_inner());
+        for (core, hashes) in core_hashes {
+            let entry = cores.entry(*core).or_default();
+            entry.total = entry.total.saturating_add(*hashes);
+            entry.rate = if seconds > 0.0 {
+                *hashes as f64 / seconds
+            } else {
+                0.0
+            };
+        }
+        drop(cores);
+        self.round_best_difficulty
+            .lock()
+            .unwrap_or_else(|err| err.into_inner())
+            .insert(authority, best_difficulty as u64);
+        self.rounds.fetch_add(1, Ordering::Relaxed);
+        self.hashing_micros
+            .fetch_add(elapsed.as_micros() as u64, Ordering::Relaxed);
+    }
+
+    pub fn record_submitted(&self) {
+        self.transactions_submitted.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Records the outcome of a submitted transaction and the time spent sending it.
+    pub fn record_sent(&self, landed: bool, elapsed: Duration) {
+        if landed {
+            self.transactions_landed.fetch_add(1, Ordering::Relaxed);
+        } else {
+            self.transactions_failed.fetch_add(1, Ordering::Relaxed);
+        }
+        self.sending_micros
+            .fetch_add(elapsed.as_micros() as u64, Ordering::Relaxed);
+    }
+
+    /// Records rewards earned, in grains.
+    pub fn record_reward(&self, amount: u64) {
+        self.rewards_earned.fetch_add(amount, Ordering::Relaxed);
+    }
+
+    /// Records the priority fee of a landed mine transaction.
+    pub fn record_priority_fee(&self, microlamports: u64, compute_unit_limit: u32) {
+        self.priority_fee.store(microlamports, Ordering::Relaxed);
+        let lamports = (microlamports as u128 * compute_unit_limit as u128).div_ceil(1_000_000);
+        self.priority_fee_paid
+            .fetch_add(lamports as u64, Ordering::Relaxed);
+    }
+
+    /// Records a failed RPC call of the given kind.
+    pub fn record_rpc_error(&self, kind: &'static str) {
+        let mut errors = self
+            .rpc_errors
+            .lock()
+            .unwrap_or_else(|err| err.into_inner());
+        *errors.entry(kind).or_default() += 1;
+    }
+
+    pub fn render(&self) -> String {
+        let mut out = String::new();
+        {
+            let cores = self
+                .core_hashes
+                .lock()
+                .unwrap_or_else(|err| err.into_inner());
+            write_header(
+                &mut out,
+                "bitz_hashes_total",
+                "counter",
+                "Hashes computed per core.",
+            );
+            for (core, hashes) in cores.iter() {
+                let _ = writeln!(
+                    out,
+                    "bitz_hashes_total{{core=\"{}\"}} {}",
+                    core, hashes.total
+                );
+            }
+            write_header(
+                &mut out,
+                "bitz_hashes_per_second",
+                "gauge",
+                "Hashes per second per core in the last round.",
+            );
+            for (core, hashes) in cores.iter() {
+                let _ = writeln!(
+                    out,
+                    "bitz_hashes_per_second{{core=\"{}\"}} {}",
+                    core, hashes.rate
+                );
+            }
+        }
+        {
+            let difficulties = self
+                .round_best_difficulty
+                .lock()
+                .unwrap_or_else(|err| err.into_inner());
+            write_header(
+                &mut out,
+                "bitz_round_best_difficulty",
+                "gauge",
+                "Best difficulty found in the last round per proof authority.",
+            );
+            for (authority, difficulty) in difficulties.iter() {
+                let _ = writeln!(
+                    out,
+                    "bitz_round_best_difficulty{{authority=\"{}\"}} {}",
+                    authority, difficulty
+                );
+            }
+        }
+        write_metric(
+            &mut out,
+            "bitz_rounds_total",
+            "counter",
+            "Hashing rounds completed.",
+            self.rounds.load(Ordering::Relaxed),
+        );
+        write_metric(
+            &mut out,
+            "bitz_transactions_submitted_total",
+            "counter",
+            "Mine transactions submitted.",
+            self.transactions_submitted.load(Ordering::Relaxed),
+        );
+        write_metric(
+            &mut out,
+            "bitz_transactions_landed_total",
+            "counter",
+            "Mine transactions confirmed.",
+            self.transactions_landed.load(Ordering::Relaxed),
+        );
+        write_metric(
+            &mut out,
+            "bitz_transactions_failed_total",
+            "counter",
+            "Mine transactions that failed.",
+            self.transactions_failed.load(Ordering::Relaxed),
+        );
+        write_metric(
+            &mut out,
+            "bitz_rewards_earned_total",
+            "counter",
+            "Rewards earned, in BITZ.",
+            amount_u64_to_f64(self.rewards_earned.load(Ordering::Relaxed)),
+        );
+        write_metric(
+            &mut out,
+            "bitz_priority_fee_microlamports",
+            "gauge",
+            "Priority fee per compute unit of the last landed mine transaction.",
+            self.priority_fee.load(Ordering::Relaxed),
+        );
+        write_metric(
+            &mut out,
+            "bitz_priority_fee_paid_lamports_total",
+            "counter",
+            "Priority fees paid by landed mine transactions, in lamports.",
+            self.priority_fee_paid.load(Ordering::Relaxed),
+        );
+        {
+            let errors = self
+                .rpc_errors
+                .lock()
+                .unwrap_or_else(|err| err.into_inner());
+            write_header(
+                &mut out,
+                "bitz_rpc_errors_total",
+                "counter",
+                "Failed RPC calls by kind.",
+            );
+            for (kind, count) in errors.iter() {
+                let _ = writeln!(out, "bitz_rpc_errors_total{{kind=\"{}\"}} {}", kind, count);
+            }
+        }
+        write_metric(
+            &mut out,
+            "bitz_hashing_seconds_total",
+            "counter",
+            "Time spent hashing rounds.",
+            self.hashing_micros.load(Ordering::Relaxed) as f64 / 1e6,
+        );
+        write_metric(
+            &mut out,
+            "bitz_sending_seconds_total",
+            "counter",
+            "Time spent in send_and_confirm for mine transactions.",
+            self.sending_micros.load(Ordering::Relaxed) as f64 / 1e6,
+        );
+        out
+    }
+}
+
+fn write_header(out: &mut String, name: &str, kind: &str, help: &str) {
+    let _ = writeln!(out, "# HELP {} {}", name, help);
+    let _ = writeln!(out, "# TYPE {} {}", name, kind);
+}
+
+fn write_metric(
+    out: &mut String,
+    name: &str,
+    kind: &str,
+    help: &str,
+    value: impl std::fmt::Display,
+) {
+    write_header(out, name, kind, help);
+    let _ = writeln!(out, "{} {}", name, value);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn best_difficulty_is_labelled_by_authority() {
+        let metrics = Metrics::default();
+        let (first, second) = (Pubkey::new_unique(), Pubkey::new_unique());
+        metrics.record_round(first, &[(0, 100)], 18, Duration::from_secs(2));
+        metrics.record_round(second, &[(1, 100)], 21, Duration::from_secs(2));
+        metrics.record_round(first, &[(0, 100)], 16, Duration::from_secs(2));
+        let out = metrics.render();
+        assert!(out.contains(&format!(
+            "bitz_round_best_difficulty{{authority=\"{}\"}} 16\n",
+            first
+        )));
+        assert!(out.contains(&format!(
+            "bitz_round_best_difficulty{{authority=\"{}\"}} 21\n",
+            second
+        )));
+        assert!(out.contains("bitz_hashes_total{core=\"0\"} 200\n"));
+        assert!(out.contains("bitz_rounds_total 3\n"));
+    }
+}
@@ -0,0 +1,58 @@
+use std::sync::Arc;
+
+use anyhow::{Context, Result};
+use log::{debug, info};
+use tokio::{
+    io::{AsyncReadExt, AsyncWriteExt},
+    net::{TcpListener, TcpStream},
+};
+
+use super::Metrics;
+
+/// Binds `addr` and serves the metrics at `/metrics` in the background.
+pub async fn serve_metrics(addr: &str, metrics: Arc<Metrics>) -> Result<()> {
+    let listener = TcpListener::bind(addr)
+        .await
+        .with_context(|| format!("Failed to bind metrics address {}", addr))?;
+    info!(
+        "Serving metrics at http://{}/metrics",
+        listener.local_addr()?
+    );
+    tokio::spawn(async move {
+        loop {
+            match listener.accept().await {
+                Ok((stream, _)) => {
+                    let metrics = metrics.clone();
+                    tokio::spawn(async move {
+                        if let Err(err) = handle_connection(stream, &metrics).await {
+                            debug!("Metrics connection failed: {}", err);
+                        }
+                    });
+                }
+                Err(err) => debug!("Failed to accept metrics connection: {}", err),
+            }
+        }
+    });
+    Ok(())
+}
+
+async fn handle_connection(mut stream: TcpStream, metrics: &Metrics) -> Result<()> {
+    // Only the request line matters, scrapers send small GET requests
+    let mut buf = [0u8; 1024];
+    let n = stream.read(&mut buf).await?;
+    let request = String::from_utf8_lossy(&buf[..n]);
+    let path = request.split_whitespace().nth(1).unwrap_or_default();
+    let (status, body) = match path {
+        "/metrics" | "/" => ("200 OK", metrics.render()),
+        _ => ("404 Not Found", "Not found\n".to_string()),
+    };
+    let response = format!(
+        "HTTP/1.1 {}\r\nContent-Type: text/plain; version=0.0.4\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        status,
+        body.len(),
+        body
+    );
+    stream.write_all(response.as_bytes()).await?;
+    stream.shutdown().await?;
+    Ok(())
+}
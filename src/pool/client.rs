@@ -0,0 +1,515 @@
+use std::time::Duration;
+
+use anyhow::{Result, bail};
+use drillx::Solution;
+use log::{debug, warn};
+use ore_pool_types::{
+    BalanceUpdate, ContributePayloadV2, Member, MemberChallenge, PoolAddress,
+    PoolMemberMiningEvent, RegisterPayload, UpdateBalancePayload,
+};
+use reqwest::{Client, Response};
+use serde::de::DeserializeOwned;
+use solana_program::pubkey::Pubkey;
+use solana_sdk::{hash::Hash, signature::Keypair, signer::Signer, transaction::Transaction};
+
+use crate::constants::MAX_POOL_EVENT_POLL_ATTEMPTS;
+
+/// How long a single request to the pool server may take.
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// How long to wait between polls of the pool server.
+const POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+/// HTTP client for an ore-pool compatible operator server.
+#[derive(Clone)]
+pub struct Pool {
+    pub http_client: Client,
+    pub pool_url: String,
+    pub poll_interval: Duration,
+}
+
+impl Pool {
+    pub fn new(pool_url: String) -> Self {
+        Self::with_timing(pool_url, REQUEST_TIMEOUT, POLL_INTERVAL)
+    }
+
+    /// Creates a client whose requests fail after `request_timeout`, and that waits
+    /// `poll_interval` between polls of the server.
+    pub fn with_timing(
+        pool_url: String,
+        request_timeout: Duration,
+        poll_interval: Duration,
+    ) -> Self {
+        let http_client = Client::builder()
+            .timeout(request_timeout)
+            .build()
+            .expect("Failed to build pool HTTP client");
+        Self {
+            http_client,
+            pool_url: pool_url.trim_end_matches('/').to_string(),
+            poll_interval,
+        }
+    }
+
+    /// Registers the authority as a pool member, or returns the existing member record.
+    pub async fn post_pool_register(&self, authority: Pubkey) -> Result<Member> {
+        let post_url = format!("{}/register", self.pool_url);
+        let body = RegisterPayload { authority };
+        let resp = self.http_client.post(post_url).json(&body).send().await?;
+        match parse_response::<Member>(resp).await {
+            Ok(member) => Ok(member),
+            Err(err) => {
+                // The server rejects duplicate registrations, fall back to the member lookup
+                debug!("Failed to register with pool: {}", err);
+                self.get_pool_member(authority)
+                    .await
+                    .map_err(|_| err.context("Failed to register with pool"))
+            }
+        }
+    }
+
+    pub async fn get_pool_address(&self) -> Result<PoolAddress> {
+        let get_url = format!("{}/pool-address", self.pool_url);
+        let resp = self.http_client.get(get_url).send().await?;
+        parse_response(resp).await
+    }
+
+    pub async fn get_pool_member(&self, authority: Pubkey) -> Result<Member> {
+        let get_url = format!("{}/member/{}", self.pool_url, authority);
+        let resp = self.http_client.get(get_url).send().await?;
+        parse_response(resp).await
+    }
+
+    pub async fn get_pool_challenge(&self, authority: Pubkey) -> Result<MemberChallenge> {
+        let get_url = format!("{}/challenge/{}", self.pool_url, authority);
+        let resp = self.http_client.get(get_url).send().await?;
+        parse_response(resp).await
+    }
+
+    /// Polls the pool until it serves a challenge newer than `last_hash_at`.
+    pub async fn get_updated_pool_challenge(
+        &self,
+        authority: Pubkey,
+        last_hash_at: i64,
+    ) -> Result<MemberChallenge> {
+        loop {
+            let member_challenge = self.get_pool_challenge(authority).await?;
+            if member_challenge.challenge.lash_hash_at.gt(&last_hash_at) {
+                return Ok(member_challenge);
+            }
+            tokio::time::sleep(self.poll_interval).await;
+        }
+    }
+
+    pub async fn post_pool_solution(&self, authority: Pubkey, solution: &Solution) -> Result<()> {
+        let post_url = format!("{}/contribute", self.pool_url);
+        let body = ContributePayloadV2 {
+            authority,
+            solution: *solution,
+        };
+        let resp = self.http_client.post(post_url).json(&body).send().await?;
+        if let Err(err) = resp.error_for_status_ref() {
+            let text = resp.text().await.unwrap_or_default();
+            bail!("{}: {}", err, text);
+        }
+        Ok(())
+    }
+
+    /// Asks the operator to attribute the member's pending balance on-chain.
+    ///
+    /// The attribute instruction is signed by the pool authority, so the client only
+    /// signs as fee payer and the server co-signs and submits the transaction.
+    pub async fn post_pool_update_balance(
+        &self,
+        signer: &Keypair,
+        pool_authority: Pubkey,
+        total_balance: u64,
+        hash: Hash,
+    ) -> Result<BalanceUpdate> {
+        let ix = ore_pool_api::sdk::attribute(pool_authority, signer.pubkey(), total_balance);
+        let mut transaction = Transaction::new_with_payer(&[ix], Some(&signer.pubkey()));
+        transaction.partial_sign(&[signer], hash);
+        let post_url = format!("{}/update-balance", self.pool_url);
+        let body = UpdateBalancePayload {
+            authority: signer.pubkey(),
+            transaction,
+            hash,
+        };
+        let resp = self.http_client.post(post_url).json(&body).send().await?;
+        parse_response(resp).await
+    }
+
+    /// Polls the pool for the mining event of the round started at `last_hash_at`.
+    /// Failed and timed out requests count as attempts and are retried.
+    pub async fn get_latest_pool_event(
+        &self,
+        authority: Pubkey,
+        last_hash_at: i64,
+    ) -> Result<PoolMemberMiningEvent> {
+        let get_url = format!("{}/event/latest/{}", self.pool_url, authority);
+        for _ in 0..MAX_POOL_EVENT_POLL_ATTEMPTS {
+            let event = match self.http_client.get(&get_url).send().await {
+                Ok(resp) => parse_response::<PoolMemberMiningEvent>(resp).await,
+                Err(err) => Err(err.into()),
+            };
+            match event {
+                Ok(event) if event.last_hash_at.eq(&last_hash_at) => return Ok(event),
+                Ok(_) => {}
+                Err(err) => warn!("Failed to fetch pool event: {}", err),
+            }
+            tokio::time::sleep(self.poll_interval).await;
+        }
+        bail!(
+            "Failed to fetch pool event after {} attempts",
+            MAX_POOL_EVENT_POLL_ATTEMPTS
+        )
+    }
+}
+
+/// Splits the nonce range assigned to a member device evenly across `cores`.
+///
+/// The pool divides the nonce space by its member count, then each member range
+/// by the number of devices allowed per member. Device ids start at 1.
+pub fn pool_nonce_indices(
+    member_id: u64,
+    member_challenge: &MemberChallenge,
+    device_id: Option<u64>,
+    cores: u64,
+) -> Result<Vec<u64>> {
+    let num_devices = (member_challenge.num_devices as u64).max(1);
+    let device_id = device_id.unwrap_or(1);
+    if device_id == 0 || device_id > num_devices {
+        bail!(
+            "Device id {} is out of range, the pool allows device ids 1 to {}",
+            device_id,
+            num_devices
+        );
+    }
+    let member_range = u64::MAX.saturating_div(member_challenge.num_total_members.max(1));
+    let device_range = member_range.saturating_div(num_devices);
+    let core_range = device_range.saturating_div(cores.max(1));
+    let left_bound = member_range
+        .saturating_mul(member_id)
+        .saturating_add(device_range.saturating_mul(device_id - 1));
+    Ok((0..cores)
+        .map(|n| left_bound.saturating_add(core_range.saturating_mul(n)))
+        .collect())
+}
+
+async fn parse_response<T: DeserializeOwned>(resp: Response) -> Result<T> {
+    if let Err(err) = resp.error_for_status_ref() {
+        let text = resp.text().await.unwrap_or_default();
+        bail!("{}: {}", err, text);
+    }
+    Ok(resp.json::<T>().await?)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        io::{BufRead, BufReader, Read, Write},
+        net::{TcpListener, TcpStream},
+        sync::{Arc, Mutex},
+        thread,
+    };
+
+    use ore_pool_types::Challenge;
+    use solana_sdk::signature::Signature;
+
+    use super::*;
+
+    /// A canned response of the stub pool server.
+    struct Reply {
+        status: u16,
+        body: String,
+        delay: Duration,
+    }
+
+    impl Reply {
+        fn json(body: &impl serde::Serialize) -> Self {
+            Self {
+                status: 200,
+                body: serde_json::to_string(body).unwrap(),
+                delay: Duration::ZERO,
+            }
+        }
+
+        fn error(status: u16, body: &str) -> Self {
+            Self {
+                status,
+                body: body.to_string(),
+                delay: Duration::ZERO,
+            }
+        }
+
+        fn delayed(mut self, delay: Duration) -> Self {
+            self.delay = delay;
+            self
+        }
+    }
+
+    /// A local HTTP server that answers every request with `handler`, given the
+    /// request line and the number of earlier requests. Returns the server url and
+    /// the request lines it received.
+    fn serve<F>(handler: F) -> (String, Arc<Mutex<Vec<String>>>)
+    where
+        F: Fn(&str, usize) -> Reply + Send + Sync + 'static,
+    {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/", listener.local_addr().unwrap());
+        let requests = Arc::new(Mutex::new(vec![]));
+        let handler = Arc::new(handler);
+        {
+            let requests = requests.clone();
+            thread::spawn(move || {
+                for stream in listener.incoming().flatten() {
+                    let request = read_request(&stream);
+                    let reply = {
+                        let mut requests = requests.lock().unwrap();
+                        requests.push(request.clone());
+                        handler(&request, requests.len() - 1)
+                    };
+                    thread::spawn(move || write_reply(stream, reply));
+                }
+            });
+        }
+        (url, requests)
+    }
+
+    fn read_request(stream: &TcpStream) -> String {
+        let mut reader = BufReader::new(stream);
+        let mut request_line = String::new();
+        reader.read_line(&mut request_line).unwrap();
+        let mut content_length = 0;
+        loop {
+            let mut header = String::new();
+            reader.read_line(&mut header).unwrap();
+            if header.trim().is_empty() {
+                break;
+            }
+            if let Some((name, value)) = header.split_once(':')
+                && name.eq_ignore_ascii_case("content-length")
+            {
+                content_length = value.trim().parse().unwrap();
+            }
+        }
+        let mut body = vec![0; content_length];
+        reader.read_exact(&mut body).unwrap();
+        request_line.trim().to_string()
+    }
+
+    fn write_reply(mut stream: TcpStream, reply: Reply) {
+        thread::sleep(reply.delay);
+        let response = format!(
+            "HTTP/1.1 {} Stub\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+            reply.status,
+            reply.body.len(),
+            reply.body
+        );
+        // The client may have given up on a delayed reply
+        let _ = stream.write_all(response.as_bytes());
+    }
+
+    fn test_pool(url: String) -> Pool {
+        Pool::with_timing(url, Duration::from_millis(200), Duration::from_millis(10))
+    }
+
+    fn member(authority: Pubkey) -> Member {
+        Member {
+            address: Pubkey::new_unique().to_string(),
+            id: 7,
+            authority: authority.to_string(),
+            pool_address: Pubkey::new_unique().to_string(),
+            total_balance: 0,
+            is_approved: true,
+            is_kyc: false,
+            is_synced: true,
+        }
+    }
+
+    // The server still sends the deprecated device id
+    #[allow(deprecated)]
+    fn member_challenge(lash_hash_at: i64) -> MemberChallenge {
+        MemberChallenge {
+            challenge: Challenge {
+                challenge: [1; 32],
+                lash_hash_at,
+                min_difficulty: 12,
+                cutoff_time: 55,
+            },
+            num_total_members: 4,
+            device_id: 1,
+            num_devices: 2,
+            unix_timestamp: lash_hash_at + 5,
+        }
+    }
+
+    fn event(last_hash_at: i64) -> PoolMemberMiningEvent {
+        PoolMemberMiningEvent {
+            signature: Signature::default(),
+            block: 1,
+            timestamp: 2,
+            balance: 3,
+            difficulty: 20,
+            last_hash_at,
+            timing: 4,
+            net_reward: 5,
+            net_base_reward: 6,
+            net_miner_boost_reward: 7,
+            net_staker_boost_reward: 8,
+            member_difficulty: 18,
+            member_reward: 9,
+        }
+    }
+
+    #[tokio::test]
+    async fn register_falls_back_to_member_lookup() {
+        let authority = Pubkey::new_unique();
+        let (url, requests) = serve(move |request, _| {
+            if request.starts_with("POST /register") {
+                Reply::error(500, "member already exists")
+            } else {
+                Reply::json(&member(authority))
+            }
+        });
+        let member = test_pool(url).post_pool_register(authority).await.unwrap();
+        assert_eq!(member.id, 7);
+        assert_eq!(member.authority, authority.to_string());
+        assert_eq!(
+            *requests.lock().unwrap(),
+            [
+                "POST /register HTTP/1.1".to_string(),
+                format!("GET /member/{} HTTP/1.1", authority),
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn register_reports_the_registration_error() {
+        let (url, _) = serve(|request, _| {
+            if request.starts_with("POST /register") {
+                Reply::error(400, "pool is full")
+            } else {
+                Reply::error(404, "member not found")
+            }
+        });
+        let err = test_pool(url)
+            .post_pool_register(Pubkey::new_unique())
+            .await
+            .unwrap_err();
+        let message = format!("{:#}", err);
+        assert!(message.starts_with("Failed to register with pool"));
+        assert!(message.contains("400"));
+        assert!(message.contains("pool is full"));
+    }
+
+    #[tokio::test]
+    async fn error_status_is_mapped_with_response_body() {
+        let (url, _) = serve(|_, _| Reply::error(503, "overloaded"));
+        let pool = test_pool(url);
+        let err = pool
+            .get_pool_challenge(Pubkey::new_unique())
+            .await
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("503"));
+        assert!(err.ends_with(": overloaded"));
+
+        let solution = Solution::new([0; 16], [0; 8]);
+        let err = pool
+            .post_pool_solution(Pubkey::new_unique(), &solution)
+            .await
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("503"));
+        assert!(err.ends_with(": overloaded"));
+    }
+
+    #[tokio::test]
+    async fn invalid_response_body_is_an_error() {
+        let (url, _) = serve(|_, _| Reply::error(200, "{\"unexpected\":true}"));
+        assert!(test_pool(url).get_pool_address().await.is_err());
+    }
+
+    #[tokio::test]
+    async fn slow_response_times_out() {
+        let (url, _) = serve(|_, _| {
+            Reply::json(&member(Pubkey::new_unique())).delayed(Duration::from_secs(2))
+        });
+        let err = test_pool(url)
+            .get_pool_member(Pubkey::new_unique())
+            .await
+            .unwrap_err();
+        let err = err.downcast_ref::<reqwest::Error>().unwrap();
+        assert!(err.is_timeout());
+    }
+
+    #[tokio::test]
+    async fn challenge_is_polled_until_a_new_round() {
+        let (url, requests) = serve(|_, n| Reply::json(&member_challenge(100 + n as i64)));
+        let member_challenge = test_pool(url)
+            .get_updated_pool_challenge(Pubkey::new_unique(), 102)
+            .await
+            .unwrap();
+        assert_eq!(member_challenge.challenge.lash_hash_at, 103);
+        assert_eq!(requests.lock().unwrap().len(), 4);
+    }
+
+    #[tokio::test]
+    async fn event_is_retried_past_stale_failed_and_timed_out_responses() {
+        let (url, requests) = serve(|_, n| match n {
+            0 => Reply::json(&event(99)),
+            1 => Reply::error(500, "database unavailable"),
+            2 => Reply::json(&event(100)).delayed(Duration::from_secs(2)),
+            _ => Reply::json(&event(100)),
+        });
+        let event = test_pool(url)
+            .get_latest_pool_event(Pubkey::new_unique(), 100)
+            .await
+            .unwrap();
+        assert_eq!(event.last_hash_at, 100);
+        assert_eq!(event.member_reward, 9);
+        assert_eq!(requests.lock().unwrap().len(), 4);
+    }
+
+    #[tokio::test]
+    async fn event_gives_up_after_max_attempts() {
+        let (url, requests) = serve(|_, _| Reply::json(&event(99)));
+        let err = test_pool(url)
+            .get_latest_pool_event(Pubkey::new_unique(), 100)
+            .await
+            .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            format!(
+                "Failed to fetch pool event after {} attempts",
+                MAX_POOL_EVENT_POLL_ATTEMPTS
+            )
+        );
+        assert_eq!(
+            requests.lock().unwrap().len(),
+            MAX_POOL_EVENT_POLL_ATTEMPTS as usize
+        );
+    }
+
+    #[test]
+    fn nonce_indices_split_the_device_range() {
+        let challenge = member_challenge(100);
+        let indices = pool_nonce_indices(1, &challenge, Some(2), 4).unwrap();
+        let member_range = u64::MAX / 4;
+        let device_range = member_range / 2;
+        let core_range = device_range / 4;
+        let first = member_range + device_range;
+        assert_eq!(
+            indices,
+            [
+                first,
+                first + core_range,
+                first + 2 * core_range,
+                first + 3 * core_range
+            ]
+        );
+        assert!(pool_nonce_indices(1, &challenge, Some(0), 4).is_err());
+        assert!(pool_nonce_indices(1, &challenge, Some(3), 4).is_err());
+    }
+}
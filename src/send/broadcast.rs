@@ -0,0 +1,105 @@
+use std::{
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+use futures::future::join_all;
+use log::{debug, info};
+use solana_client::{nonblocking::rpc_client::RpcClient, rpc_config::RpcSendTransactionConfig};
+use solana_sdk::transaction::Transaction;
+
+use crate::Miner;
+
+/// An RPC endpoint transactions are broadcast through.
+pub struct SendEndpoint {
+    pub url: String,
+    pub client: Arc<RpcClient>,
+    stats: Mutex<EndpointStats>,
+}
+
+#[derive(Default)]
+struct EndpointStats {
+    submitted: u64,
+    landed: u64,
+    errors: u64,
+    accepted: u64,
+    latency: Duration,
+}
+
+impl SendEndpoint {
+    pub fn new(url: String, client: Arc<RpcClient>) -> Self {
+        Self {
+            url,
+            client,
+            stats: Mutex::new(EndpointStats::default()),
+        }
+    }
+}
+
+impl Miner {
+    /// Sends the transaction through every endpoint in parallel and marks the ones
+    /// that accepted it. Returns whether any endpoint accepted it.
+    pub(super) async fn broadcast(
+        &self,
+        tx: &Transaction,
+        config: RpcSendTransactionConfig,
+        accepted: &mut [bool],
+    ) -> bool {
+        let sends = self.send_endpoints.iter().map(|endpoint| async move {
+            let timer = Instant::now();
+            let result = endpoint
+                .client
+                .send_transaction_with_config(tx, config)
+                .await;
+            (endpoint, result, timer.elapsed())
+        });
+        let mut any_accepted = false;
+        for (index, (endpoint, result, elapsed)) in join_all(sends).await.into_iter().enumerate() {
+            let mut stats = endpoint.stats.lock().unwrap_or_else(|err| err.into_inner());
+            match result {
+                Ok(_) => {
+                    stats.accepted += 1;
+                    stats.latency += elapsed;
+                    accepted[index] = true;
+                    any_accepted = true;
+                }
+                Err(err) => {
+                    stats.errors += 1;
+                    self.metrics.record_rpc_error("send_transaction");
+                    debug!("Error submitting transaction to {}: {}", endpoint.url, err);
+                }
+            }
+        }
+        any_accepted
+    }
+
+    /// Counts a signed transaction against the endpoints it was sent through and
+    /// logs their land rates.
+    pub(super) fn record_broadcast(&self, accepted: &[bool], landed: bool) {
+        for (endpoint, accepted) in self.send_endpoints.iter().zip(accepted) {
+            let mut stats = endpoint.stats.lock().unwrap_or_else(|err| err.into_inner());
+            stats.submitted += 1;
+            if landed && *accepted {
+                stats.landed += 1;
+            }
+            let message = format!(
+                "{}: landed {}/{} ({:.0}%), {} send errors, {} ms average send latency",
+                endpoint.url,
+                stats.landed,
+                stats.submitted,
+                stats.landed as f64 / stats.submitted as f64 * 100.0,
+                stats.errors,
+                stats
+                    .latency
+                    .as_millis()
+                    .checked_div(stats.accepted as u128)
+                    .unwrap_or_default()
+            );
+            if self.send_endpoints.len() > 1 {
+                info!("{}", message);
+            } else {
+                debug!("{}", message);
+            }
+        }
+    }
+}
@@ -0,0 +1,188 @@
+use std::time::Duration;
+
+use futures::StreamExt;
+use log::{debug, warn};
+use solana_client::{
+    nonblocking::pubsub_client::PubsubClient,
+    rpc_config::{RpcSendTransactionConfig, RpcSignatureSubscribeConfig},
+    rpc_response::RpcSignatureResult,
+};
+use solana_sdk::{
+    commitment_config::CommitmentConfig,
+    signature::Signature,
+    transaction::{Transaction, TransactionError},
+};
+use solana_transaction_status::TransactionConfirmationStatus;
+use tokio::time::{Instant, sleep, timeout};
+
+use crate::Miner;
+
+/// How long to wait for a confirmation before rebroadcasting the transaction.
+const REBROADCAST_INTERVAL: Duration = Duration::from_secs(2);
+
+/// How long the websocket handshake and the signature subscription may take before
+/// falling back to polling signature statuses.
+const SUBSCRIBE_TIMEOUT: Duration = Duration::from_secs(3);
+
+pub enum Confirmation {
+    /// The transaction reached the confirmed commitment level.
+    Confirmed,
+    /// The transaction was executed and failed.
+    Failed(TransactionError),
+    /// The blockhash expired before the transaction landed.
+    Expired,
+    /// The deadline passed before the transaction landed.
+    TimedOut,
+}
+
+impl Miner {
+    /// Broadcasts the signed transaction through every send endpoint until the
+    /// primary RPC confirms it, it fails, its blockhash expires past
+    /// `last_valid_block_height`, or `deadline` passes.
+    ///
+    /// Confirmations arrive over `signatureSubscribe`. If the websocket is not
+    /// available or does not answer within `SUBSCRIBE_TIMEOUT`, signature statuses
+    /// are polled over RPC instead.
+    pub async fn send_until_confirmed(
+        &self,
+        tx: &Transaction,
+        send_config: RpcSendTransactionConfig,
+        last_valid_block_height: u64,
+        deadline: Instant,
+    ) -> Confirmation {
+        let sig = tx.signatures[0];
+
+        // Subscribe before sending so the notification cannot be missed
+        let subscribe_timeout =
+            SUBSCRIBE_TIMEOUT.min(deadline.saturating_duration_since(Instant::now()));
+        let pubsub = match timeout(subscribe_timeout, PubsubClient::new(&self.websocket_url)).await
+        {
+            Ok(Ok(pubsub)) => Some(pubsub),
+            Ok(Err(err)) => {
+                warn!("Failed to connect to {}: {}", self.websocket_url, err);
+                None
+            }
+            Err(_) => {
+                warn!(
+                    "Timed out connecting to {}, polling statuses",
+                    self.websocket_url
+                );
+                None
+            }
+        };
+        let mut notifications = match &pubsub {
+            Some(pubsub) => {
+                let config = RpcSignatureSubscribeConfig {
+                    commitment: Some(CommitmentConfig::confirmed()),
+                    enable_received_notification: Some(false),
+                };
+                match timeout(
+                    subscribe_timeout,
+                    pubsub.signature_subscribe(&sig, Some(config)),
+                )
+                .await
+                {
+                    Ok(Ok((notifications, _))) => Some(notifications),
+                    Ok(Err(err)) => {
+                        warn!("Failed to subscribe to signature {}: {}", sig, err);
+                        None
+                    }
+                    Err(_) => {
+                        warn!(
+                            "Timed out subscribing to signature {}, polling statuses",
+                            sig
+                        );
+                        None
+                    }
+                }
+            }
+            None => None,
+        };
+
+        let mut accepted = vec![false; self.send_endpoints.len()];
+        let confirmation = loop {
+            if Instant::now() >= deadline {
+                break Confirmation::TimedOut;
+            }
+
+            // Rebroadcast, the endpoints are asked not to retry on their own
+            debug!("Sending transaction {}", sig);
+            self.broadcast(tx, send_config, &mut accepted).await;
+
+            // Wait for the confirmation
+            let wait = REBROADCAST_INTERVAL.min(deadline.saturating_duration_since(Instant::now()));
+            let result = match notifications.as_mut() {
+                Some(stream) => match timeout(wait, stream.next()).await {
+                    Ok(Some(response)) => match response.value {
+                        RpcSignatureResult::ProcessedSignature(result) => Some(result.err),
+                        RpcSignatureResult::ReceivedSignature(_) => None,
+                    },
+                    Ok(None) => {
+                        warn!("Signature subscription closed, polling statuses");
+                        notifications = None;
+                        None
+                    }
+                    Err(_) => None,
+                },
+                None => {
+                    sleep(wait).await;
+                    self.get_signature_result(&sig).await
+                }
+            };
+            if let Some(err) = result {
+                break match err {
+                    Some(err) => Confirmation::Failed(err),
+                    None => Confirmation::Confirmed,
+                };
+            }
+
+            // Check for blockhash expiry
+            match self.rpc_client.get_block_height().await {
+                Ok(block_height) if block_height > last_valid_block_height => {
+                    // The transaction may have landed just before expiring
+                    break match self.get_signature_result(&sig).await {
+                        Some(Some(err)) => Confirmation::Failed(err),
+                        Some(None) => Confirmation::Confirmed,
+                        None => Confirmation::Expired,
+                    };
+                }
+                Ok(_) => {}
+                Err(err) => {
+                    self.metrics.record_rpc_error("block_height");
+                    debug!("Error getting block height: {}", err);
+                }
+            }
+        };
+        self.record_broadcast(
+            &accepted,
+            matches!(
+                confirmation,
+                Confirmation::Confirmed | Confirmation::Failed(_)
+            ),
+        );
+        confirmation
+    }
+
+    /// Returns the result of a confirmed transaction, or `None` if it has not been
+    /// confirmed yet.
+    async fn get_signature_result(&self, sig: &Signature) -> Option<Option<TransactionError>> {
+        match self.rpc_client.get_signature_statuses(&[*sig]).await {
+            Ok(statuses) => {
+                let status = statuses.value.into_iter().next().flatten()?;
+                if status.err.is_some() {
+                    return Some(status.err);
+                }
+                match status.confirmation_status {
+                    Some(TransactionConfirmationStatus::Confirmed)
+                    | Some(TransactionConfirmationStatus::Finalized) => Some(None),
+                    _ => None,
+                }
+            }
+            Err(err) => {
+                self.metrics.record_rpc_error("signature_status");
+                warn!("Error getting signature status: {}", err);
+                None
+            }
+        }
+    }
+}
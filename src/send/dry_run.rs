@@ -0,0 +1,291 @@
+use b64::FromBase64;
+use colored::Colorize;
+use eore_api::event::MineEvent;
+use solana_client::{
+    client_error::{ClientError, ClientErrorKind, Result as ClientResult},
+    rpc_config::RpcSimulateTransactionConfig,
+};
+use solana_sdk::{
+    compute_budget,
+    instruction::CompiledInstruction,
+    message::{AccountKeys, Message},
+    native_token::lamports_to_sol,
+    signature::Signature,
+    transaction::Transaction,
+};
+use solana_transaction_status::{
+    UiTransactionEncoding, UiTransactionReturnData, parse_instruction,
+};
+use tabled::{
+    Table,
+    settings::{
+        Alignment, Remove, Style,
+        object::{Columns, Rows},
+    },
+};
+
+use crate::{
+    Miner,
+    utils::{TableData, TableSectionTitle, amount_u64_to_f64},
+};
+
+impl Miner {
+    /// Simulates a signed transaction against the current bank and prints its
+    /// instructions, compute units, logs and fee instead of sending it.
+    pub(super) async fn dry_run_transaction(&self, tx: &Transaction) -> ClientResult<Signature> {
+        let config = RpcSimulateTransactionConfig {
+            sig_verify: true,
+            replace_recent_blockhash: false,
+            commitment: Some(self.rpc_client.commitment()),
+            encoding: Some(UiTransactionEncoding::Base64),
+            ..Default::default()
+        };
+        let result = self
+            .rpc_client
+            .simulate_transaction_with_config(tx, config)
+            .await?
+            .value;
+        let fee = self.rpc_client.get_fee_for_message(&tx.message).await?;
+        let (compute_unit_limit, compute_unit_price) = compute_budget_of(&tx.message);
+        let priority_fee = (compute_unit_limit.unwrap_or_default() as u64
+            * compute_unit_price.unwrap_or_default())
+        .div_ceil(1_000_000);
+
+        let mut data = vec![
+            TableData {
+                key: "Signature".to_string(),
+                value: tx.signatures[0].to_string(),
+            },
+            TableData {
+                key: "Fee payer".to_string(),
+                value: tx.message.account_keys[0].to_string(),
+            },
+            TableData {
+                key: "Size".to_string(),
+                value: format!("{} bytes", bincode::serialized_size(tx).unwrap_or_default()),
+            },
+            TableData {
+                key: "Result".to_string(),
+                value: match &result.err {
+                    Some(err) => format!("{} {}", "FAILED".bold().red(), err),
+                    None => "OK".bold().green().to_string(),
+                },
+            },
+            TableData {
+                key: "Compute units".to_string(),
+                value: format!(
+                    "{} / {}",
+                    result
+                        .units_consumed
+                        .map_or("-".to_string(), |units| units.to_string()),
+                    compute_unit_limit.map_or("-".to_string(), |units| units.to_string())
+                ),
+            },
+            TableData {
+                key: "Compute unit price".to_string(),
+                value: format!("{} microlamports", compute_unit_price.unwrap_or_default()),
+            },
+            TableData {
+                key: "Priority fee".to_string(),
+                value: format!("{} ETH", lamports_to_sol(priority_fee)),
+            },
+            TableData {
+                key: "Transaction fee".to_string(),
+                value: format!("{} ETH", lamports_to_sol(fee)),
+            },
+        ];
+        let event_row = data.len();
+        let event = result.return_data.as_ref().and_then(mine_event);
+        if let Some(event) = &event {
+            data.extend([
+                TableData {
+                    key: "Difficulty".to_string(),
+                    value: event.difficulty.to_string(),
+                },
+                TableData {
+                    key: "Timing".to_string(),
+                    value: format!("{} sec", event.timing),
+                },
+                TableData {
+                    key: "Base reward".to_string(),
+                    value: format!("{} BITZ", amount_u64_to_f64(event.net_base_reward)),
+                },
+                TableData {
+                    key: "Boost reward".to_string(),
+                    value: format!("{} BITZ", amount_u64_to_f64(event.net_miner_boost_reward)),
+                },
+                TableData {
+                    key: "Total reward".to_string(),
+                    value: format!("{} BITZ", amount_u64_to_f64(event.net_reward)),
+                },
+            ]);
+        }
+        let mut table = Table::new(data);
+        table.with(Remove::row(Rows::first()));
+        table.modify(Columns::single(1), Alignment::right());
+        table.with(Style::blank());
+        table.section_title(0, "Transaction");
+        table.section_title(3, "Simulation");
+        if event.is_some() {
+            table.section_title(event_row, "Mine event");
+        }
+        println!("{table}\n");
+
+        println!("{}", "Instructions".bold());
+        for (i, ix) in tx.message.instructions.iter().enumerate() {
+            println!("  #{} {}", i, describe_instruction(&tx.message, ix));
+        }
+        println!("\n{}", "Logs".bold());
+        for log in result.logs.unwrap_or_default() {
+            println!("  {}", log);
+        }
+        println!();
+
+        match result.err {
+            Some(err) => Err(ClientError {
+                request: None,
+                kind: ClientErrorKind::Custom(format!("Simulation failed: {}", err)),
+            }),
+            None => Ok(tx.signatures[0]),
+        }
+    }
+}
+
+/// Decodes the mine event an ORE mine instruction returns.
+fn mine_event(return_data: &UiTransactionReturnData) -> Option<MineEvent> {
+    if return_data.program_id != eore_api::ID.to_string() {
+        return None;
+    }
+    let bytes = return_data.data.0.from_base64().ok()?;
+    (bytes.len() == size_of::<MineEvent>()).then(|| *MineEvent::from_bytes(&bytes))
+}
+
+/// A one line description of a compiled instruction, decoded for the programs the
+/// CLI sends to.
+fn describe_instruction(message: &Message, ix: &CompiledInstruction) -> String {
+    let program_id = message.account_keys[ix.program_id_index as usize];
+    let account_keys = AccountKeys::new(&message.account_keys, None);
+    if let Ok(parsed) = parse_instruction::parse(&program_id, ix, &account_keys, None) {
+        return format!("{}: {}", parsed.program, parsed.parsed);
+    }
+    let (program, name) = if program_id == compute_budget::ID {
+        ("Compute Budget", decode_compute_budget(&ix.data))
+    } else if program_id == eore_api::ID {
+        (
+            "ORE",
+            decode_steel::<eore_api::instruction::OreInstruction>(&ix.data),
+        )
+    } else if program_id == eore_boost_api::ID {
+        (
+            "Boost",
+            decode_steel::<eore_boost_api::instruction::BoostInstruction>(&ix.data),
+        )
+    } else if program_id == ore_pool_api::ID {
+        (
+            "Pool",
+            decode_steel::<ore_pool_api::instruction::PoolInstruction>(&ix.data),
+        )
+    } else {
+        return format!("{}: {} bytes of data", program_id, ix.data.len());
+    };
+    let accounts = ix
+        .accounts
+        .iter()
+        .map(|index| message.account_keys[*index as usize].to_string())
+        .collect::<Vec<_>>();
+    if accounts.is_empty() {
+        format!("{}: {}", program, name)
+    } else {
+        format!("{}: {} [{}]", program, name, accounts.join(", "))
+    }
+}
+
+/// The compute unit limit and price a message sets, if any.
+fn compute_budget_of(message: &Message) -> (Option<u32>, Option<u64>) {
+    let mut limit = None;
+    let mut price = None;
+    for ix in &message.instructions {
+        if message.account_keys[ix.program_id_index as usize] != compute_budget::ID {
+            continue;
+        }
+        match ix.data.first() {
+            Some(2) => limit = read_u32(&ix.data),
+            Some(3) => price = read_u64(&ix.data),
+            _ => {}
+        }
+    }
+    (limit, price)
+}
+
+fn decode_compute_budget(data: &[u8]) -> String {
+    let arg = match data.first() {
+        Some(1) => ("RequestHeapFrame", read_u32(data).map(u64::from)),
+        Some(2) => ("SetComputeUnitLimit", read_u32(data).map(u64::from)),
+        Some(3) => ("SetComputeUnitPrice", read_u64(data)),
+        Some(4) => (
+            "SetLoadedAccountsDataSizeLimit",
+            read_u32(data).map(u64::from),
+        ),
+        _ => return format!("Unknown({:?})", data),
+    };
+    match arg {
+        (name, Some(value)) => format!("{}({})", name, value),
+        (name, None) => format!("{}(?)", name),
+    }
+}
+
+/// Reads the little endian argument following the one byte discriminator.
+fn read_u32(data: &[u8]) -> Option<u32> {
+    data.get(1..5)?.try_into().ok().map(u32::from_le_bytes)
+}
+
+fn read_u64(data: &[u8]) -> Option<u64> {
+    data.get(1..9)?.try_into().ok().map(u64::from_le_bytes)
+}
+
+/// Decodes the discriminator of a steel instruction, and its amount argument when
+/// the instruction data starts with one.
+fn decode_steel<T: TryFrom<u8> + std::fmt::Debug>(data: &[u8]) -> String {
+    let Some(name) = data.first().and_then(|d| T::try_from(*d).ok()) else {
+        return format!("Unknown({:?})", data);
+    };
+    let name = format!("{:?}", name);
+    let has_amount = matches!(name.as_str(), "Claim" | "Deposit" | "Withdraw");
+    match read_u64(data) {
+        Some(amount) if has_amount => format!("{}({})", name, amount),
+        _ => name,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use solana_sdk::{pubkey::Pubkey, transaction_context::TransactionReturnData};
+
+    use super::*;
+
+    fn return_data(program_id: Pubkey, data: Vec<u8>) -> UiTransactionReturnData {
+        TransactionReturnData { program_id, data }.into()
+    }
+
+    #[test]
+    fn mine_event_is_decoded_from_ore_return_data() {
+        let event = MineEvent {
+            balance: 1,
+            difficulty: 21,
+            last_hash_at: 2,
+            timing: -3,
+            net_reward: 40,
+            net_base_reward: 30,
+            net_miner_boost_reward: 10,
+            net_staker_boost_reward: 5,
+        };
+        let bytes = bytemuck::bytes_of(&event).to_vec();
+
+        assert_eq!(
+            mine_event(&return_data(eore_api::ID, bytes.clone())),
+            Some(event)
+        );
+        assert_eq!(mine_event(&return_data(Pubkey::new_unique(), bytes)), None);
+        assert_eq!(mine_event(&return_data(eore_api::ID, vec![1, 2, 3])), None);
+    }
+}
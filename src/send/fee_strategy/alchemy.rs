@@ -0,0 +1,60 @@
+use anyhow::{Result, anyhow};
+use serde_json::{Value, json};
+use solana_program::pubkey::Pubkey;
+
+use super::FeeStrategy;
+
+/// Alchemy `getRecentPrioritizationFees`, averaged with a 20% premium.
+pub struct AlchemyFeeStrategy;
+
+impl FeeStrategy for AlchemyFeeStrategy {
+    fn name(&self) -> &'static str {
+        "alchemy"
+    }
+
+    fn matches_host(&self, host: &str) -> bool {
+        host.contains("alchemy.com")
+    }
+
+    fn request_body(&self, accounts: &[Pubkey]) -> Value {
+        json!({
+            "jsonrpc": "2.0",
+            "id": "priority-fee-estimate",
+            "method": "getRecentPrioritizationFees",
+            "params": [
+                accounts.iter().map(|a| a.to_string()).collect::<Vec<_>>()
+            ]
+        })
+    }
+
+    fn parse_response(&self, response: &Value) -> Result<u64> {
+        response["result"]
+            .as_array()
+            .map(|arr| {
+                arr.iter()
+                    .filter_map(|v| v["prioritizationFee"].as_u64())
+                    .collect::<Vec<u64>>()
+            })
+            .filter(|fees| !fees.is_empty())
+            .map(|fees| ((fees.iter().sum::<u64>() as f32 / fees.len() as f32).ceil() * 1.2) as u64)
+            .ok_or_else(|| anyhow!("Failed to parse priority fee response: {:?}", response))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn averages_recent_fees_with_a_premium() {
+        let response = serde_json::from_str(include_str!("fixtures/alchemy.json")).unwrap();
+        // (0 + 1000 + 2000 + 0 + 1500) / 5 * 1.2
+        assert_eq!(AlchemyFeeStrategy.parse_response(&response).unwrap(), 1080);
+    }
+
+    #[test]
+    fn empty_fees_are_an_error() {
+        let response = json!({"jsonrpc": "2.0", "result": [], "id": "priority-fee-estimate"});
+        assert!(AlchemyFeeStrategy.parse_response(&response).is_err());
+    }
+}
@@ -0,0 +1,67 @@
+use anyhow::{Result, anyhow};
+use serde_json::{Value, json};
+use solana_program::pubkey::Pubkey;
+
+use super::FeeStrategy;
+
+/// Helius `getPriorityFeeEstimate` with the recommended fee level.
+pub struct HeliusFeeStrategy;
+
+impl FeeStrategy for HeliusFeeStrategy {
+    fn name(&self) -> &'static str {
+        "helius"
+    }
+
+    fn matches_host(&self, host: &str) -> bool {
+        host.contains("helius-rpc.com")
+    }
+
+    fn request_body(&self, accounts: &[Pubkey]) -> Value {
+        json!({
+            "jsonrpc": "2.0",
+            "id": "priority-fee-estimate",
+            "method": "getPriorityFeeEstimate",
+            "params": [{
+                "accountKeys": accounts.iter().map(|a| a.to_string()).collect::<Vec<_>>(),
+                "options": {
+                    "recommended": true
+                }
+            }]
+        })
+    }
+
+    fn parse_response(&self, response: &Value) -> Result<u64> {
+        response["result"]["priorityFeeEstimate"]
+            .as_f64()
+            .map(|fee| fee as u64)
+            .ok_or_else(|| anyhow!("Failed to parse priority fee response: {:?}", response))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_the_recommended_fee() {
+        let response = serde_json::from_str(include_str!("fixtures/helius.json")).unwrap();
+        assert_eq!(HeliusFeeStrategy.parse_response(&response).unwrap(), 120000);
+    }
+
+    #[test]
+    fn missing_estimate_is_an_error() {
+        let response = json!({"jsonrpc": "2.0", "result": {}, "id": "priority-fee-estimate"});
+        assert!(HeliusFeeStrategy.parse_response(&response).is_err());
+    }
+
+    #[test]
+    fn requests_fees_for_every_account() {
+        let accounts = [Pubkey::new_unique(), Pubkey::new_unique()];
+        let body = HeliusFeeStrategy.request_body(&accounts);
+        assert_eq!(body["method"], "getPriorityFeeEstimate");
+        assert_eq!(
+            body["params"][0]["accountKeys"],
+            json!([accounts[0].to_string(), accounts[1].to_string()])
+        );
+    }
+}
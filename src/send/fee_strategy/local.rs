@@ -0,0 +1,153 @@
+use std::sync::Mutex;
+
+use anyhow::{Result, anyhow, bail};
+use serde_json::{Value, json};
+use solana_client::rpc_response::RpcPrioritizationFee;
+use solana_program::pubkey::Pubkey;
+
+use super::FeeStrategy;
+
+/// The number of most recent slots the estimate is taken over.
+const FEE_WINDOW_SLOTS: usize = 150;
+
+/// The standard `getRecentPrioritizationFees` method, supported by any RPC.
+///
+/// Takes a percentile of the fees paid to write the queried accounts in the most
+/// recent 150 slots, optionally smoothed with an exponential moving average across
+/// estimates.
+pub struct LocalFeeStrategy {
+    percentile: u8,
+    ema_alpha: Option<f64>,
+    ema: Mutex<Option<f64>>,
+}
+
+impl LocalFeeStrategy {
+    /// Creates an estimator for the given percentile (0 to 100). When `ema_alpha` is
+    /// set, each estimate is weighted by it against the previous average.
+    pub fn new(percentile: u8, ema_alpha: Option<f64>) -> Self {
+        Self {
+            percentile: percentile.min(100),
+            ema_alpha,
+            ema: Mutex::new(None),
+        }
+    }
+
+    fn smooth(&self, fee: u64) -> u64 {
+        let Some(alpha) = self.ema_alpha else {
+            return fee;
+        };
+        let mut ema = self.ema.lock().unwrap_or_else(|err| err.into_inner());
+        let average = match *ema {
+            Some(previous) => alpha * fee as f64 + (1.0 - alpha) * previous,
+            None => fee as f64,
+        };
+        *ema = Some(average);
+        average.round() as u64
+    }
+}
+
+impl Default for LocalFeeStrategy {
+    fn default() -> Self {
+        Self::new(75, None)
+    }
+}
+
+impl FeeStrategy for LocalFeeStrategy {
+    fn name(&self) -> &'static str {
+        "local"
+    }
+
+    fn matches_host(&self, _host: &str) -> bool {
+        false
+    }
+
+    fn request_body(&self, accounts: &[Pubkey]) -> Value {
+        json!({
+            "jsonrpc": "2.0",
+            "id": "priority-fee-estimate",
+            "method": "getRecentPrioritizationFees",
+            "params": [
+                accounts.iter().map(|a| a.to_string()).collect::<Vec<_>>()
+            ]
+        })
+    }
+
+    fn parse_response(&self, response: &Value) -> Result<u64> {
+        let mut recent_fees =
+            serde_json::from_value::<Vec<RpcPrioritizationFee>>(response["result"].clone())
+                .map_err(|error| {
+                    anyhow!("Failed to parse priority fee response: {response:?}, error: {error}")
+                })?;
+        recent_fees.sort_by_key(|fee| std::cmp::Reverse(fee.slot));
+
+        // Slots without any fee paid are part of the window, so an idle network
+        // estimates zero rather than the last fee someone paid
+        let fees = recent_fees
+            .iter()
+            .take(FEE_WINDOW_SLOTS)
+            .map(|fee| fee.prioritization_fee)
+            .collect::<Vec<_>>();
+        let Some(fee) = percentile(&fees, self.percentile) else {
+            bail!("No recent prioritization fees");
+        };
+        Ok(self.smooth(fee))
+    }
+}
+
+/// Returns the nearest-rank percentile of the fees, or `None` if there are none.
+fn percentile(fees: &[u64], percentile: u8) -> Option<u64> {
+    let mut sorted_fees = fees.to_vec();
+    sorted_fees.sort_unstable();
+    let rank = (percentile as usize * sorted_fees.len()).div_ceil(100);
+    sorted_fees.get(rank.saturating_sub(1)).copied()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fixture() -> Value {
+        serde_json::from_str(include_str!("fixtures/local.json")).unwrap()
+    }
+
+    #[test]
+    fn takes_a_percentile_of_the_recent_window() {
+        // The 150 most recent slots paid 0 to 1490, the 10 before them are left out
+        assert_eq!(
+            LocalFeeStrategy::new(75, None)
+                .parse_response(&fixture())
+                .unwrap(),
+            1120
+        );
+        assert_eq!(
+            LocalFeeStrategy::new(50, None)
+                .parse_response(&fixture())
+                .unwrap(),
+            740
+        );
+        assert_eq!(
+            LocalFeeStrategy::new(100, None)
+                .parse_response(&fixture())
+                .unwrap(),
+            1490
+        );
+    }
+
+    #[test]
+    fn smooths_estimates_with_an_ema() {
+        let strategy = LocalFeeStrategy::new(100, Some(0.5));
+        assert_eq!(strategy.parse_response(&fixture()).unwrap(), 1490);
+        let idle = json!({"result": [{"slot": 1, "prioritizationFee": 0}]});
+        assert_eq!(strategy.parse_response(&idle).unwrap(), 745);
+    }
+
+    #[test]
+    fn empty_fees_are_an_error() {
+        let response = json!({"jsonrpc": "2.0", "result": [], "id": "priority-fee-estimate"});
+        assert!(
+            LocalFeeStrategy::default()
+                .parse_response(&response)
+                .is_err()
+        );
+    }
+}
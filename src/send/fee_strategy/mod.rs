@@ -0,0 +1,106 @@
+mod alchemy;
+mod helius;
+mod local;
+mod quiknode;
+mod triton;
+pub use alchemy::*;
+pub use helius::*;
+pub use local::*;
+pub use quiknode::*;
+pub use triton::*;
+
+use std::sync::Arc;
+
+use anyhow::{Result, anyhow};
+use serde_json::Value;
+use solana_program::pubkey::Pubkey;
+use url::Url;
+
+/// A priority fee estimator served over JSON-RPC.
+///
+/// Implementations build the provider specific request and parse its response, so
+/// each provider is tested against the recorded responses in `fixtures/` without a
+/// network.
+pub trait FeeStrategy: Send + Sync {
+    /// The name selected with `--fee-strategy`.
+    fn name(&self) -> &'static str;
+
+    /// Whether this provider serves RPC requests for the given host.
+    fn matches_host(&self, host: &str) -> bool;
+
+    /// Builds the JSON-RPC request estimating fees for transactions writing `accounts`.
+    fn request_body(&self, accounts: &[Pubkey]) -> Value;
+
+    /// Parses the JSON-RPC response into a fee in microlamports per compute unit.
+    fn parse_response(&self, response: &Value) -> Result<u64>;
+}
+
+/// All known fee strategies, in the order they are matched against a host.
+pub fn fee_strategies(local: Arc<LocalFeeStrategy>) -> Vec<Arc<dyn FeeStrategy>> {
+    vec![
+        Arc::new(HeliusFeeStrategy),
+        Arc::new(AlchemyFeeStrategy),
+        Arc::new(QuiknodeFeeStrategy),
+        Arc::new(TritonFeeStrategy),
+        local,
+    ]
+}
+
+pub fn fee_strategy_by_name(
+    name: &str,
+    local: Arc<LocalFeeStrategy>,
+) -> Result<Arc<dyn FeeStrategy>> {
+    let strategies = fee_strategies(local);
+    let names = strategies
+        .iter()
+        .map(|strategy| strategy.name())
+        .collect::<Vec<_>>()
+        .join(", ");
+    strategies
+        .into_iter()
+        .find(|strategy| strategy.name().eq_ignore_ascii_case(name))
+        .ok_or_else(|| anyhow!("Unknown fee strategy {}, expected one of: {}", name, names))
+}
+
+/// Guesses the fee strategy from the RPC host, falling back to the local estimator.
+pub fn fee_strategy_for_url(rpc_url: &str, local: Arc<LocalFeeStrategy>) -> Arc<dyn FeeStrategy> {
+    let host = Url::parse(rpc_url)
+        .ok()
+        .and_then(|url| url.host_str().map(str::to_string))
+        .unwrap_or_default();
+    fee_strategies(local.clone())
+        .into_iter()
+        .find(|strategy| strategy.matches_host(&host))
+        .unwrap_or(local)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn strategy_is_guessed_from_the_host() {
+        let local = Arc::new(LocalFeeStrategy::default());
+        let name = |url| fee_strategy_for_url(url, local.clone()).name();
+        assert_eq!(name("https://mainnet.helius-rpc.com/?api-key=x"), "helius");
+        assert_eq!(name("https://eclipse.g.alchemy.com/v2/x"), "alchemy");
+        assert_eq!(name("https://x.solana-mainnet.quiknode.pro/y/"), "quiknode");
+        assert_eq!(name("https://x.rpcpool.com/y"), "triton");
+        assert_eq!(name("https://mainnetbeta-rpc.eclipse.xyz"), "local");
+        assert_eq!(name("not a url"), "local");
+    }
+
+    #[test]
+    fn strategy_is_selected_by_name() {
+        let local = Arc::new(LocalFeeStrategy::default());
+        let strategy = fee_strategy_by_name("Triton", local.clone()).unwrap();
+        assert_eq!(strategy.name(), "triton");
+        let Err(err) = fee_strategy_by_name("unknown", local) else {
+            panic!("Unknown fee strategy was selected");
+        };
+        assert!(
+            err.to_string()
+                .contains("helius, alchemy, quiknode, triton, local")
+        );
+    }
+}
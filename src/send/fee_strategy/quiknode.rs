@@ -0,0 +1,71 @@
+use anyhow::{Result, anyhow};
+use serde_json::{Value, json};
+use solana_program::pubkey::Pubkey;
+
+use super::FeeStrategy;
+
+/// QuickNode `qn_estimatePriorityFees` at the medium fee level.
+pub struct QuiknodeFeeStrategy;
+
+impl FeeStrategy for QuiknodeFeeStrategy {
+    fn name(&self) -> &'static str {
+        "quiknode"
+    }
+
+    fn matches_host(&self, host: &str) -> bool {
+        host.contains("quiknode.pro")
+    }
+
+    fn request_body(&self, _accounts: &[Pubkey]) -> Value {
+        // QuickNode estimates fees for a single account. The program is invoked by
+        // every miner, while the proof is written only by our own transactions.
+        json!({
+            "jsonrpc": "2.0",
+            "id": "1",
+            "method": "qn_estimatePriorityFees",
+            "params": {
+                "account": eore_api::ID.to_string(),
+                "last_n_blocks": 100
+            }
+        })
+    }
+
+    fn parse_response(&self, response: &Value) -> Result<u64> {
+        response["result"]["per_compute_unit"]["medium"]
+            .as_f64()
+            .map(|fee| fee as u64)
+            .ok_or_else(|| {
+                anyhow!(
+                    "Please enable the Solana Priority Fee API add-on in your QuickNode account."
+                )
+            })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_the_medium_fee() {
+        let response = serde_json::from_str(include_str!("fixtures/quiknode.json")).unwrap();
+        assert_eq!(
+            QuiknodeFeeStrategy.parse_response(&response).unwrap(),
+            50000
+        );
+    }
+
+    #[test]
+    fn missing_add_on_is_an_error() {
+        let response = json!({"jsonrpc": "2.0", "error": {"code": -32601}, "id": 1});
+        let err = QuiknodeFeeStrategy.parse_response(&response).unwrap_err();
+        assert!(err.to_string().contains("Priority Fee API add-on"));
+    }
+
+    #[test]
+    fn requests_fees_for_the_program() {
+        let body = QuiknodeFeeStrategy.request_body(&[Pubkey::new_unique()]);
+        assert_eq!(body["method"], "qn_estimatePriorityFees");
+        assert_eq!(body["params"]["account"], eore_api::ID.to_string());
+    }
+}
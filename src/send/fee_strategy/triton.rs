@@ -0,0 +1,81 @@
+use anyhow::{Result, anyhow, bail};
+use serde_json::{Value, json};
+use solana_client::rpc_response::RpcPrioritizationFee;
+use solana_program::pubkey::Pubkey;
+
+use super::FeeStrategy;
+
+/// Triton `getRecentPrioritizationFees` with the median percentile extension,
+/// averaged over the most recent 20 slots.
+pub struct TritonFeeStrategy;
+
+impl FeeStrategy for TritonFeeStrategy {
+    fn name(&self) -> &'static str {
+        "triton"
+    }
+
+    fn matches_host(&self, host: &str) -> bool {
+        host.contains("rpcpool.com")
+    }
+
+    fn request_body(&self, accounts: &[Pubkey]) -> Value {
+        json!({
+            "jsonrpc": "2.0",
+            "id": "priority-fee-estimate",
+            "method": "getRecentPrioritizationFees",
+            "params": [
+                accounts.iter().map(|a| a.to_string()).collect::<Vec<_>>(),
+                {
+                    "percentile": 5000,
+                }
+            ]
+        })
+    }
+
+    fn parse_response(&self, response: &Value) -> Result<u64> {
+        let prioritization_fees =
+            serde_json::from_value::<Vec<RpcPrioritizationFee>>(response["result"].clone())
+                .map_err(|error| {
+                    anyhow!("Failed to parse priority fee response: {response:?}, error: {error}")
+                })?;
+        let prioritization_fees = prioritization_fees
+            .into_iter()
+            .rev()
+            .take(20)
+            .map(
+                |RpcPrioritizationFee {
+                     prioritization_fee, ..
+                 }| prioritization_fee,
+            )
+            .collect::<Vec<_>>();
+        if prioritization_fees.is_empty() {
+            bail!("Response does not contain any prioritization fees");
+        }
+
+        Ok(prioritization_fees.iter().sum::<u64>() / prioritization_fees.len() as u64)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn averages_the_last_20_slots() {
+        let response = serde_json::from_str(include_str!("fixtures/triton.json")).unwrap();
+        // The last 20 slots paid 100 to 2000, the 5 before them are left out
+        assert_eq!(TritonFeeStrategy.parse_response(&response).unwrap(), 1050);
+    }
+
+    #[test]
+    fn empty_fees_are_an_error() {
+        let response = json!({"jsonrpc": "2.0", "result": [], "id": "priority-fee-estimate"});
+        assert!(TritonFeeStrategy.parse_response(&response).is_err());
+    }
+
+    #[test]
+    fn requests_the_median_percentile() {
+        let body = TritonFeeStrategy.request_body(&[Pubkey::new_unique()]);
+        assert_eq!(body["params"][1]["percentile"], 5000);
+    }
+}
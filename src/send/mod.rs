@@ -1,5 +1,13 @@
+mod broadcast;
+mod confirm;
+mod dry_run;
+mod fee_strategy;
+mod offline;
 mod priority_fee;
 mod send_and_confirm;
-
-
-
+mod simulate;
+pub use broadcast::*;
+pub use fee_strategy::*;
+pub use offline::*;
+pub use send_and_confirm::*;
+pub use simulate::compute_unit_limit;
@@ -0,0 +1,357 @@
+use std::{fs, path::Path};
+
+use anyhow::{Context, Result, anyhow, bail};
+use b64::{FromBase64, STANDARD, ToBase64};
+use clap::ValueEnum;
+use serde::{Deserialize, Serialize};
+use solana_sdk::{
+    hash::Hash,
+    sanitize::Sanitize,
+    signature::{Keypair, Signature},
+    signer::Signer,
+    transaction::Transaction,
+};
+
+use crate::{Miner, args::OfflineArgs, utils::BlockhashSource};
+
+/// How a transaction is built and where it goes once signed.
+#[derive(Clone, Default)]
+pub struct SendOptions {
+    pub blockhash: BlockhashSource,
+    /// Sign with the local keypairs but do not broadcast.
+    pub sign_only: bool,
+    /// Write the transaction to this file instead of broadcasting it.
+    pub export_tx: Option<String>,
+    pub encoding: TxEncoding,
+    /// A blockhash, or the value of the durable nonce, given on the command line
+    /// instead of being fetched.
+    pub fixed_blockhash: Option<Hash>,
+}
+
+impl SendOptions {
+    pub fn new(blockhash: BlockhashSource, offline: &OfflineArgs) -> Self {
+        Self {
+            blockhash,
+            sign_only: offline.sign_only,
+            export_tx: offline.export_tx.clone(),
+            encoding: offline.tx_encoding,
+            fixed_blockhash: offline.blockhash,
+        }
+    }
+
+    /// Whether the transaction is written out instead of broadcast.
+    pub fn is_offline(&self) -> bool {
+        self.sign_only || self.export_tx.is_some()
+    }
+
+    /// Fails under `--sign-only`, which makes no RPC calls, for commands that have to
+    /// read on-chain state to build their transaction.
+    pub fn require_rpc(&self, reason: &str) -> Result<()> {
+        if self.sign_only {
+            bail!(
+                "--sign-only makes no RPC calls, but {}. Export the transaction with --export-tx and sign it offline with `bitz sign` instead.",
+                reason
+            );
+        }
+        Ok(())
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize, ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum TxEncoding {
+    Base58,
+    #[default]
+    Base64,
+}
+
+/// A serialized transaction, as written by `--export-tx` and read by `bitz submit`.
+#[derive(Serialize, Deserialize)]
+pub struct ExportedTransaction {
+    pub encoding: TxEncoding,
+    pub transaction: String,
+    /// The block height the blockhash expires at, `None` for durable nonces and
+    /// blockhashes given with `--blockhash`.
+    pub last_valid_block_height: Option<u64>,
+    pub signers: Vec<ExportedSigner>,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct ExportedSigner {
+    pub pubkey: String,
+    pub signature: Option<String>,
+}
+
+impl ExportedTransaction {
+    pub fn new(
+        tx: &Transaction,
+        encoding: TxEncoding,
+        last_valid_block_height: Option<u64>,
+    ) -> Result<Self> {
+        let bytes = bincode::serialize(tx)?;
+        let transaction = match encoding {
+            TxEncoding::Base58 => bs58::encode(bytes).into_string(),
+            TxEncoding::Base64 => bytes.to_base64(STANDARD),
+        };
+        let num_signers = tx.message.header.num_required_signatures as usize;
+        let signers = tx
+            .message
+            .account_keys
+            .iter()
+            .take(num_signers)
+            .zip(&tx.signatures)
+            .map(|(pubkey, signature)| ExportedSigner {
+                pubkey: pubkey.to_string(),
+                signature: (*signature != Signature::default()).then(|| signature.to_string()),
+            })
+            .collect();
+        Ok(Self {
+            encoding,
+            transaction,
+            last_valid_block_height,
+            signers,
+        })
+    }
+
+    pub fn read(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        let json = fs::read_to_string(path)
+            .with_context(|| format!("Failed to read {}", path.display()))?;
+        serde_json::from_str(&json).with_context(|| format!("Failed to parse {}", path.display()))
+    }
+
+    pub fn write(&self, path: impl AsRef<Path>) -> Result<()> {
+        let path = path.as_ref();
+        fs::write(path, self.to_json()?)
+            .with_context(|| format!("Failed to write {}", path.display()))
+    }
+
+    pub fn to_json(&self) -> Result<String> {
+        Ok(serde_json::to_string_pretty(self)? + "\n")
+    }
+
+    pub fn transaction(&self) -> Result<Transaction> {
+        let bytes = match self.encoding {
+            TxEncoding::Base58 => bs58::decode(&self.transaction).into_vec()?,
+            TxEncoding::Base64 => self
+                .transaction
+                .from_base64()
+                .map_err(|err| anyhow!("Invalid base64 transaction: {}", err))?,
+        };
+        let tx: Transaction = bincode::deserialize(&bytes)?;
+        tx.sanitize()?;
+        Ok(tx)
+    }
+}
+
+impl Miner {
+    /// Sets the blockhash of an unsigned transaction, signs it with the local keypairs
+    /// if `--sign-only` is set, and writes it to the export file or stdout.
+    pub(super) fn export_transaction(
+        &self,
+        tx: &mut Transaction,
+        hash: Hash,
+        last_valid_block_height: u64,
+        options: &SendOptions,
+    ) -> Result<Signature> {
+        tx.message.recent_blockhash = hash;
+        if options.sign_only {
+            let signer = self.try_signer()?;
+            let fee_payer = self.try_fee_payer()?;
+            sign_partial(tx, &[&signer, &fee_payer])?;
+        }
+        let exported = ExportedTransaction::new(
+            tx,
+            options.encoding,
+            (last_valid_block_height != u64::MAX).then_some(last_valid_block_height),
+        )?;
+        match &options.export_tx {
+            Some(path) => {
+                exported.write(path)?;
+                println!("Transaction written to {}", path);
+            }
+            None => print!("{}", exported.to_json()?),
+        }
+        Ok(tx.signatures[0])
+    }
+}
+
+/// Adds the signatures of `keypairs` that the transaction still needs, and fails
+/// if any signature is still missing.
+pub fn sign_missing(tx: &mut Transaction, keypairs: &[&Keypair]) -> Result<()> {
+    sign_partial(tx, keypairs)?;
+    if !tx.is_signed() {
+        bail!(
+            "Transaction is missing signatures from {}",
+            missing_signers(tx).join(", ")
+        );
+    }
+    Ok(())
+}
+
+/// Adds the signatures of `keypairs` that the transaction still needs, and returns
+/// how many were added. Keypairs that are not signers of the transaction are ignored.
+pub fn sign_partial(tx: &mut Transaction, keypairs: &[&Keypair]) -> Result<usize> {
+    let blockhash = tx.message.recent_blockhash;
+    let mut signed = 0;
+    for keypair in keypairs {
+        let Some(index) = tx
+            .message
+            .account_keys
+            .iter()
+            .take(tx.message.header.num_required_signatures as usize)
+            .position(|pubkey| *pubkey == keypair.pubkey())
+        else {
+            continue;
+        };
+        if tx.signatures[index] == Signature::default() {
+            tx.try_partial_sign(&[*keypair], blockhash)?;
+            signed += 1;
+        }
+    }
+    Ok(signed)
+}
+
+/// The signers whose signatures the transaction still needs.
+pub fn missing_signers(tx: &Transaction) -> Vec<String> {
+    tx.message
+        .account_keys
+        .iter()
+        .zip(&tx.signatures)
+        .filter(|(_, signature)| **signature == Signature::default())
+        .map(|(pubkey, _)| pubkey.to_string())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use solana_client::nonblocking::rpc_client::RpcClient;
+    use solana_sdk::{pubkey::Pubkey, system_instruction};
+
+    use super::*;
+    use crate::{
+        metrics::Metrics,
+        send::{LocalFeeStrategy, SendEndpoint},
+        utils::ComputeBudget,
+    };
+
+    /// A miner whose keypair files do not exist and whose RPC is unreachable.
+    fn offline_miner(signer_pubkey: Option<Pubkey>) -> Miner {
+        let missing = std::env::temp_dir()
+            .join(format!("bitz-missing-keypair-{}.json", std::process::id()))
+            .to_string_lossy()
+            .to_string();
+        let url = "http://127.0.0.1:1".to_string();
+        let rpc_client = Arc::new(RpcClient::new(url.clone()));
+        Miner::new(
+            rpc_client.clone(),
+            String::new(),
+            Arc::new(vec![SendEndpoint::new(url, rpc_client)]),
+            None,
+            Some(missing.clone()),
+            None,
+            false,
+            Arc::new(LocalFeeStrategy::new(50, None)),
+            10,
+            false,
+            Some(missing),
+            signer_pubkey,
+            signer_pubkey,
+            None,
+            Arc::default(),
+            Arc::default(),
+            Arc::new(Metrics::default()),
+        )
+    }
+
+    fn transfer_tx(fee_payer: &Keypair, signer: &Keypair) -> Transaction {
+        let mut tx = Transaction::new_with_payer(
+            &[system_instruction::transfer(
+                &signer.pubkey(),
+                &Pubkey::new_unique(),
+                1,
+            )],
+            Some(&fee_payer.pubkey()),
+        );
+        tx.message.recent_blockhash = Hash::new_unique();
+        tx
+    }
+
+    #[test]
+    fn sign_partial_adds_only_missing_signatures() {
+        let fee_payer = Keypair::new();
+        let signer = Keypair::new();
+        let mut tx = transfer_tx(&fee_payer, &signer);
+
+        assert_eq!(
+            sign_partial(&mut tx, &[&signer, &Keypair::new()]).unwrap(),
+            1
+        );
+        assert_eq!(missing_signers(&tx), vec![fee_payer.pubkey().to_string()]);
+        assert_eq!(sign_partial(&mut tx, &[&signer]).unwrap(), 0);
+        assert!(sign_missing(&mut tx, &[&signer]).is_err());
+
+        sign_missing(&mut tx, &[&fee_payer]).unwrap();
+        assert!(tx.is_signed());
+        tx.verify().unwrap();
+    }
+
+    #[test]
+    fn exported_transaction_round_trips() {
+        let fee_payer = Keypair::new();
+        let signer = Keypair::new();
+        let mut tx = transfer_tx(&fee_payer, &signer);
+        sign_partial(&mut tx, &[&signer]).unwrap();
+
+        for encoding in [TxEncoding::Base58, TxEncoding::Base64] {
+            let exported = ExportedTransaction::new(&tx, encoding, None).unwrap();
+            assert_eq!(exported.signers[0].signature, None);
+            assert_eq!(
+                exported.signers[1].signature,
+                Some(tx.signatures[1].to_string())
+            );
+            let json = exported.to_json().unwrap();
+            let read: ExportedTransaction = serde_json::from_str(&json).unwrap();
+            assert_eq!(read.transaction().unwrap(), tx);
+            assert_eq!(read.last_valid_block_height, None);
+        }
+    }
+
+    #[tokio::test]
+    async fn unsigned_export_needs_no_keypair_file() {
+        let signer = Pubkey::new_unique();
+        let blockhash = Hash::new_unique();
+        let path = std::env::temp_dir().join(format!("bitz-export-{}.json", std::process::id()));
+        let options = SendOptions {
+            export_tx: Some(path.to_string_lossy().to_string()),
+            fixed_blockhash: Some(blockhash),
+            ..Default::default()
+        };
+        let ixs = [system_instruction::transfer(
+            &signer,
+            &Pubkey::new_unique(),
+            1,
+        )];
+
+        offline_miner(Some(signer))
+            .send_and_confirm_with_options(&ixs, ComputeBudget::Fixed(10_000), false, &options)
+            .await
+            .unwrap();
+        let exported = ExportedTransaction::read(&path).unwrap();
+        let _ = fs::remove_file(&path);
+        let tx = exported.transaction().unwrap();
+        assert_eq!(tx.message.account_keys[0], signer);
+        assert_eq!(tx.message.recent_blockhash, blockhash);
+        assert!(exported.signers.iter().all(|s| s.signature.is_none()));
+
+        // Without an address to build for, the missing keypair is an error
+        assert!(
+            offline_miner(None)
+                .send_and_confirm_with_options(&ixs, ComputeBudget::Fixed(10_000), false, &options)
+                .await
+                .is_err()
+        );
+    }
+}
@@ -1,4 +1,3 @@
-#![allow(dead_code)]
 use std::time::Duration;
 
 use colored::Colorize;
@@ -12,59 +11,157 @@ use solana_sdk::commitment_config::CommitmentLevel;
 use solana_sdk::compute_budget::ComputeBudgetInstruction;
 use solana_sdk::instruction::Instruction;
 use solana_sdk::native_token::{lamports_to_sol, sol_to_lamports};
+use solana_sdk::signature::Signature;
+use solana_sdk::system_instruction;
 use solana_sdk::transaction::Transaction;
-use solana_sdk::{signature::Signature, signer::Signer};
-use solana_transaction_status::{TransactionConfirmationStatus, UiTransactionEncoding};
+use solana_transaction_status::UiTransactionEncoding;
+use tokio::time::Instant;
 
-use crate::utils::get_latest_blockhash_with_retries;
-use crate::{Miner, utils::ComputeBudget};
-
-const MIN_ETH_BALANCE: f64 = 0.0005;
+use super::confirm::Confirmation;
+use super::offline::SendOptions;
+use super::simulate::compute_unit_limit;
+use crate::utils::{BlockhashSource, get_latest_blockhash_with_retries, get_nonce};
+use crate::{Miner, constants::MIN_ETH_BALANCE, utils::ComputeBudget};
 
 const RPC_RETRIES: usize = 0;
-const _SIMULATION_RETRIES: usize = 4;
-const GATEWAY_RETRIES: usize = 150;
-const CONFIRM_RETRIES: usize = 8;
 
-const CONFIRM_DELAY: u64 = 500;
-const GATEWAY_DELAY: u64 = 0;
+/// How long to keep resending a transaction, across blockhashes, before giving up.
+const SEND_TIMEOUT: Duration = Duration::from_secs(180);
+
+/// The compute unit limit of `--sign-only` transactions with a dynamic budget, which
+/// cannot be simulated without RPC access.
+const SIGN_ONLY_COMPUTE_UNIT_LIMIT: u32 = 400_000;
+
+/// What the last attempt of a send used, kept even when the send fails.
+#[derive(Default)]
+pub struct SendAttempt {
+    /// The signature of the last broadcast attempt.
+    pub signature: Option<Signature>,
+    /// The compute unit price, in microlamports.
+    pub priority_fee: u64,
+    pub compute_unit_limit: u32,
+}
+
 impl Miner {
     pub async fn send_and_confirm(
         &self,
         ixs: &[Instruction],
         compute_budget: ComputeBudget,
         skip_confirm: bool,
+    ) -> ClientResult<Signature> {
+        self.send_and_confirm_with_options(
+            ixs,
+            compute_budget,
+            skip_confirm,
+            &SendOptions::default(),
+        )
+        .await
+    }
+
+    /// Like `send_and_confirm`, signing with the blockhash source of `options`. Offline
+    /// options write the transaction out instead of sending it, and return its
+    /// signature if it was signed.
+    pub async fn send_and_confirm_with_options(
+        &self,
+        ixs: &[Instruction],
+        compute_budget: ComputeBudget,
+        skip_confirm: bool,
+        options: &SendOptions,
+    ) -> ClientResult<Signature> {
+        self.send_and_confirm_tracked(
+            ixs,
+            compute_budget,
+            skip_confirm,
+            options,
+            &mut SendAttempt::default(),
+        )
+        .await
+    }
+
+    /// Like `send_and_confirm_with_options`, keeping the signature and fees of the
+    /// last broadcast attempt in `attempt`, so a failed send can still be traced.
+    pub async fn send_and_confirm_tracked(
+        &self,
+        ixs: &[Instruction],
+        compute_budget: ComputeBudget,
+        skip_confirm: bool,
+        options: &SendOptions,
+        attempt: &mut SendAttempt,
     ) -> ClientResult<Signature> {
         debug!("Starting send_and_confirm with {} instructions", ixs.len());
 
         let progress_bar = spinner::new_progress_bar();
-        let signer = self.signer();
         let client = self.rpc_client.clone();
-        let fee_payer = self.fee_payer();
 
-        debug!("Using signer: {}", signer.pubkey());
-        debug!("Using fee payer: {}", fee_payer.pubkey());
+        // Only the addresses are needed to build the transaction, so an unsigned export
+        // works without the keypairs
+        let (signer, fee_payer) = self
+            .signer_pubkey()
+            .and_then(|signer| Ok((signer, self.fee_payer_pubkey()?)))
+            .map_err(|err| {
+                log_error(&progress_bar, &err.to_string(), true);
+                custom_error(err)
+            })?;
+
+        debug!("Using signer: {}", signer);
+        debug!("Using fee payer: {}", fee_payer);
         debug!("RPC client URL: {}", client.url());
 
-        // Return error, if balance is zero
-        self.check_balance().await;
+        // Return error, if balance is zero. Offline transactions are paid for later, and
+        // a dry run pays nothing.
+        if !options.is_offline() && !self.dry_run {
+            self.check_balance().await.map_err(|err| {
+                log_error(&progress_bar, &err.to_string(), true);
+                custom_error(err)
+            })?;
+        }
+
+        // The nonce advance must be the first instruction
+        let mut final_ixs = match options.blockhash {
+            BlockhashSource::Recent => vec![],
+            BlockhashSource::DurableNonce(address) => {
+                debug!("Using durable nonce account {}", address);
+                vec![system_instruction::advance_nonce_account(&address, &signer)]
+            }
+        };
 
         // Set compute budget
-        let mut final_ixs = vec![];
-        match compute_budget {
+        let mut priority_fee = self.priority_fee.unwrap_or(0);
+        let cus = match compute_budget {
+            ComputeBudget::Dynamic if options.sign_only => {
+                debug!(
+                    "Using sign-only compute budget: {} CUs",
+                    SIGN_ONLY_COMPUTE_UNIT_LIMIT
+                );
+                SIGN_ONLY_COMPUTE_UNIT_LIMIT
+            }
             ComputeBudget::Dynamic => {
-                debug!("Using dynamic compute budget");
-                todo!("simulate tx")
+                progress_bar.set_message("Simulating transaction...");
+                let sim_ixs = [final_ixs.as_slice(), ixs].concat();
+                let units_consumed = self
+                    .simulate_compute_units(&sim_ixs, &fee_payer, priority_fee)
+                    .await
+                    .inspect_err(|err| {
+                        self.metrics.record_rpc_error("simulate");
+                        log_error(&progress_bar, &err.to_string(), true)
+                    })?;
+                let cus = compute_unit_limit(units_consumed, self.compute_unit_margin);
+                debug!(
+                    "Using dynamic compute budget: {} CUs ({} simulated, {}% margin)",
+                    cus, units_consumed, self.compute_unit_margin
+                );
+                cus
             }
             ComputeBudget::Fixed(cus) => {
                 debug!("Using fixed compute budget: {} CUs", cus);
-                final_ixs.push(ComputeBudgetInstruction::set_compute_unit_limit(cus))
+                cus
             }
-        }
+        };
+        final_ixs.push(ComputeBudgetInstruction::set_compute_unit_limit(cus));
 
         // Set compute unit price
-        let priority_fee = self.priority_fee.unwrap_or(0);
         debug!("Setting compute unit price: {} microlamports", priority_fee);
+        let price_index = final_ixs.len();
         final_ixs.push(ComputeBudgetInstruction::set_compute_unit_price(
             priority_fee,
         ));
@@ -103,164 +200,259 @@ impl Miner {
 
         // Build tx
         debug!("Building transaction with config: skip_preflight=true, commitment=Confirmed");
-        let _send_cfg = RpcSendTransactionConfig {
+        let send_cfg = RpcSendTransactionConfig {
             skip_preflight: true,
             preflight_commitment: Some(CommitmentLevel::Confirmed),
             encoding: Some(UiTransactionEncoding::Base64),
             max_retries: Some(RPC_RETRIES),
             min_context_slot: None,
         };
-        let mut tx = Transaction::new_with_payer(&final_ixs, Some(&fee_payer.pubkey()));
+        let mut tx = Transaction::new_with_payer(&final_ixs, Some(&fee_payer));
 
-        // Submit tx
+        // Submit tx, signing it with a new blockhash whenever the last one expires
+        let deadline = Instant::now() + SEND_TIMEOUT;
         let mut attempts = 0;
         loop {
             debug!("Transaction attempt #{}", attempts);
-            progress_bar.set_message(format!("Submitting transaction... (attempt {})", attempts,));
 
-            // Sign tx with a new blockhash (after approximately ~45 sec)
-            if attempts % 10 == 0 {
-                debug!(
-                    "Refreshing blockhash and recomputing fees (attempt {})",
-                    attempts
-                );
+            // Reset the compute unit price
+            if self.dynamic_fee && !options.sign_only {
+                debug!("Computing dynamic priority fee");
+                priority_fee = match self.get_priority_fee().await {
+                    Ok(fee) => {
+                        debug!("Dynamic priority fee computed: {} microlamports", fee);
+                        fee
+                    }
+                    Err(err) => {
+                        self.metrics.record_rpc_error("priority_fee");
+                        let fee = self.priority_fee.unwrap_or(0);
+                        warn!(
+                            "Failed to get dynamic fee: {}. Falling back to static value: {} microlamports",
+                            err, fee
+                        );
+                        log_warning(
+                            &progress_bar,
+                            &format!(
+                                "{} Falling back to static value: {} microlamports",
+                                err, fee
+                            ),
+                        );
+                        fee
+                    }
+                };
 
-                // Reset the compute unit price
-                // if self.dynamic_fee {
-                //     debug!("Computing dynamic priority fee");
-                //     let fee = match self.get_dynamic_priority_fee().await {
-                //         Ok(fee) => {
-                //             debug!("Dynamic priority fee computed: {} microlamports", fee);
-                //             progress_bar.println(format!("  Priority fee: {} microlamports", fee));
-                //             fee
-                //         }
-                //         Err(err) => {
-                //             let fee = self.priority_fee.unwrap_or(0);
-                //             warn!("Failed to get dynamic fee: {}. Falling back to static value: {} microlamports", err, fee);
-                //             log_warning(
-                //                 &progress_bar,
-                //                 &format!(
-                //                     "{} Falling back to static value: {} microlamports",
-                //                     err, fee
-                //                 ),
-                //             );
-                //             fee
-                //         }
-                //     };
-
-                //     final_ixs.remove(1);
-                //     final_ixs.insert(1, ComputeBudgetInstruction::set_compute_unit_price(fee));
-                //     tx = Transaction::new_with_payer(&final_ixs, Some(&fee_payer.pubkey()));
-                // }
-
-                // Resign the tx
-                debug!("Getting latest blockhash");
-                let (hash, slot) = get_latest_blockhash_with_retries(&client).await?;
-                debug!("Got blockhash {} at slot {}", hash, slot);
-
-                if signer.pubkey() == fee_payer.pubkey() {
-                    debug!("Signing transaction with single signer");
-                    tx.sign(&[&signer], hash);
-                } else {
-                    debug!("Signing transaction with both signer and fee payer");
-                    tx.sign(&[&signer, &fee_payer], hash);
+                final_ixs[price_index] =
+                    ComputeBudgetInstruction::set_compute_unit_price(priority_fee);
+                tx = Transaction::new_with_payer(&final_ixs, Some(&fee_payer));
+            }
+
+            // Resign the tx
+            let fixed_blockhash = options.fixed_blockhash.filter(|_| options.is_offline());
+            let (hash, last_valid_block_height) = match (fixed_blockhash, options.blockhash) {
+                // Given on the command line, so its expiry is unknown
+                (Some(hash), _) => (hash, u64::MAX),
+                (None, BlockhashSource::Recent) => {
+                    debug!("Getting latest blockhash");
+                    get_latest_blockhash_with_retries(&client)
+                        .await
+                        .inspect_err(|_| self.metrics.record_rpc_error("blockhash"))?
+                }
+                (None, BlockhashSource::DurableNonce(address)) => {
+                    // A durable nonce stays valid until it is advanced
+                    debug!("Getting durable nonce");
+                    let nonce = get_nonce(&client, address).await.map_err(|err| {
+                        self.metrics.record_rpc_error("nonce");
+                        ClientError {
+                            request: None,
+                            kind: ClientErrorKind::Custom(err.to_string()),
+                        }
+                    })?;
+                    (nonce.blockhash(), u64::MAX)
                 }
+            };
+            debug!(
+                "Got blockhash {} valid until block height {}",
+                hash, last_valid_block_height
+            );
+
+            // Write the transaction out for offline signing or a later submit
+            if options.is_offline() {
+                return self
+                    .export_transaction(&mut tx, hash, last_valid_block_height, options)
+                    .inspect(|_| progress_bar.finish_and_clear())
+                    .map_err(|err| {
+                        log_error(&progress_bar, &err.to_string(), true);
+                        custom_error(err)
+                    });
             }
 
-            // Send transaction
-            attempts += 1;
-            debug!("Sending transaction to RPC");
-            match client.send_and_confirm_transaction(&tx).await {
-                Ok(sig) => {
-                    debug!("Transaction sent successfully: {}", sig);
-
-                    // Skip confirmation
-                    if skip_confirm {
-                        debug!("Skipping confirmation as requested");
-                        progress_bar.finish_with_message(format!("Sent: {}", sig));
-                        return Ok(sig);
-                    }
+            let (signer_keypair, fee_payer_keypair) = self
+                .try_signer()
+                .and_then(|signer| Ok((signer, self.try_fee_payer()?)))
+                .map_err(|err| {
+                    log_error(&progress_bar, &err.to_string(), true);
+                    custom_error(err)
+                })?;
+            // Fails rather than panics if --signer-pubkey does not match the keypair
+            let signed = if signer == fee_payer {
+                debug!("Signing transaction with single signer");
+                tx.try_sign(&[&signer_keypair], hash)
+            } else {
+                debug!("Signing transaction with both signer and fee payer");
+                tx.try_sign(&[&signer_keypair, &fee_payer_keypair], hash)
+            };
+            signed.map_err(|err| {
+                log_error(&progress_bar, &err.to_string(), true);
+                custom_error(err)
+            })?;
+            let sig = tx.signatures[0];
+
+            // Simulate instead of sending
+            if self.dry_run {
+                progress_bar.finish_and_clear();
+                return self.dry_run_transaction(&tx).await;
+            }
 
-                    // Confirm transaction
-                    'confirm: for confirm_attempt in 0..CONFIRM_RETRIES {
-                        debug!(
-                            "Confirmation attempt #{} for signature {}",
-                            confirm_attempt, sig
-                        );
-                        tokio::time::sleep(Duration::from_millis(CONFIRM_DELAY)).await;
-                        match client.get_signature_statuses(&[sig]).await {
-                            Ok(signature_statuses) => {
-                                debug!("Got signature statuses: {:?}", signature_statuses);
-                                for status in signature_statuses.value {
-                                    let status = match status {
-                                        Some(status) => status,
-                                        None => continue,
-                                    };
-
-                                    if let Some(err) = status.err {
-                                        match handle_transaction_error(err, &progress_bar) {
-                                            TransactionErrorResult::RetryTransaction => {
-                                                attempts = 0;
-                                                break 'confirm;
-                                            }
-                                            TransactionErrorResult::PropagateError(err) => {
-                                                return Err(err);
-                                            }
-                                        }
-                                    }
-                                    if let Some(confirmation) = status.confirmation_status {
-                                        if let Some(sig) = handle_confirmation_status(
-                                            confirmation,
-                                            sig,
-                                            &progress_bar,
-                                        ) {
-                                            return Ok(sig);
-                                        }
-                                    }
-                                }
-                            }
-
-                            // Handle confirmation errors
-                            Err(err) => {
-                                warn!("Error getting signature status: {}", err);
-                                log_error(&progress_bar, &err.kind().to_string(), false);
-                            }
-                        }
-                    }
+            *attempt = SendAttempt {
+                signature: Some(sig),
+                priority_fee,
+                compute_unit_limit: cus,
+            };
+
+            // Skip confirmation
+            if skip_confirm {
+                debug!("Skipping confirmation as requested");
+                let mut accepted = vec![false; self.send_endpoints.len()];
+                if !self.broadcast(&tx, send_cfg, &mut accepted).await {
+                    let err = "No RPC endpoint accepted the transaction".to_string();
+                    log_error(&progress_bar, &err, true);
+                    return Err(ClientError {
+                        request: None,
+                        kind: ClientErrorKind::Custom(err),
+                    });
                 }
+                progress_bar.finish_with_message(format!("Sent: {}", sig));
+                return Ok(sig);
+            }
 
-                // Handle submit errors
-                Err(err) => {
-                    error!("Error submitting transaction: {}", err);
-                    log_error(&progress_bar, &err.kind().to_string(), false);
+            // Send transaction
+            progress_bar.set_message(format!(
+                "Submitting transaction... (attempt {}, priority fee {} microlamports)",
+                attempts, priority_fee
+            ));
+            attempts += 1;
+            match self
+                .send_until_confirmed(&tx, send_cfg, last_valid_block_height, deadline)
+                .await
+            {
+                Confirmation::Confirmed => {
+                    debug!("Transaction confirmed");
+                    progress_bar.finish_with_message(format!("{} {}", "OK".bold().green(), sig));
+                    return Ok(sig);
+                }
+                Confirmation::Failed(err) => match handle_transaction_error(err, &progress_bar) {
+                    TransactionErrorResult::RetryTransaction => {}
+                    TransactionErrorResult::PropagateError(err) => return Err(*err),
+                },
+                Confirmation::Expired => {
+                    debug!("Blockhash expired for {}", sig);
+                    log_warning(&progress_bar, "Blockhash expired. Retrying...");
+                }
+                Confirmation::TimedOut => {
+                    let err = format!(
+                        "Transaction not confirmed after {} seconds",
+                        SEND_TIMEOUT.as_secs()
+                    );
+                    log_error(&progress_bar, &err, true);
+                    return Err(ClientError {
+                        request: None,
+                        kind: ClientErrorKind::Custom(err),
+                    });
                 }
             }
         }
     }
 
-    pub async fn check_balance(&self) {
-        debug!("Checking balance for signer: {}", self.signer().pubkey());
-        let balance = self
-            .rpc_client
-            .get_balance(&self.signer().pubkey())
+    /// Broadcasts an already signed transaction until it is confirmed, fails, or its
+    /// blockhash expires past `last_valid_block_height`.
+    pub async fn submit_and_confirm(
+        &self,
+        tx: &Transaction,
+        last_valid_block_height: u64,
+    ) -> ClientResult<Signature> {
+        if self.dry_run {
+            return self.dry_run_transaction(tx).await;
+        }
+        let progress_bar = spinner::new_progress_bar();
+        let sig = tx.signatures[0];
+        let send_cfg = RpcSendTransactionConfig {
+            skip_preflight: true,
+            preflight_commitment: Some(CommitmentLevel::Confirmed),
+            encoding: Some(UiTransactionEncoding::Base64),
+            max_retries: Some(RPC_RETRIES),
+            min_context_slot: None,
+        };
+        progress_bar.set_message(format!("Submitting transaction {}...", sig));
+        let deadline = Instant::now() + SEND_TIMEOUT;
+        let err = match self
+            .send_until_confirmed(tx, send_cfg, last_valid_block_height, deadline)
             .await
-            .unwrap_or(0);
+        {
+            Confirmation::Confirmed => {
+                progress_bar.finish_with_message(format!("{} {}", "OK".bold().green(), sig));
+                return Ok(sig);
+            }
+            Confirmation::Failed(err) => match handle_transaction_error(err, &progress_bar) {
+                // A signed transaction cannot be rebuilt, so there is nothing to retry
+                TransactionErrorResult::RetryTransaction => {
+                    "Transaction failed and must be exported again".to_string()
+                }
+                TransactionErrorResult::PropagateError(err) => return Err(*err),
+            },
+            Confirmation::Expired => {
+                "Blockhash expired. Export the transaction again, or use --nonce".to_string()
+            }
+            Confirmation::TimedOut => format!(
+                "Transaction not confirmed after {} seconds",
+                SEND_TIMEOUT.as_secs()
+            ),
+        };
+        log_error(&progress_bar, &err, true);
+        Err(ClientError {
+            request: None,
+            kind: ClientErrorKind::Custom(err),
+        })
+    }
+
+    /// Fails if the fee payer holds less than `MIN_ETH_BALANCE`. A balance that
+    /// cannot be fetched passes, the send fails on its own if it is too low.
+    pub async fn check_balance(&self) -> anyhow::Result<()> {
+        // The fee payer covers fees and rent, signers may hold no ETH at all
+        let fee_payer = self.fee_payer_pubkey()?;
+        debug!("Checking balance for fee payer: {}", fee_payer);
+        let balance = match self.rpc_client.get_balance(&fee_payer).await {
+            Ok(balance) => balance,
+            Err(err) => {
+                self.metrics.record_rpc_error("balance");
+                warn!("Failed to check the fee payer balance: {}", err);
+                return Ok(());
+            }
+        };
 
         if balance < sol_to_lamports(MIN_ETH_BALANCE) {
-            let error_msg = format!(
+            anyhow::bail!(
                 "Insufficient balance: {} ETH < {} ETH",
                 lamports_to_sol(balance),
                 MIN_ETH_BALANCE
             );
-            error!("{error_msg}");
-            panic!("{error_msg}");
         }
+        Ok(())
     }
 }
 
 enum TransactionErrorResult {
-    RetryTransaction,            // break 'confirm
-    PropagateError(ClientError), // return Err
+    RetryTransaction,                 // break 'confirm
+    PropagateError(Box<ClientError>), // return Err
 }
 
 fn handle_transaction_error(
@@ -276,27 +468,27 @@ fn handle_transaction_error(
                 solana_program::instruction::InstructionError::Custom(err_code) => match err_code {
                     e if e == OreError::NeedsReset as u32 => {
                         debug!("Needs reset error, retrying transaction");
-                        log_error(&progress_bar, "Needs reset. Retrying...", false);
+                        log_error(progress_bar, "Needs reset. Retrying...", false);
                         TransactionErrorResult::RetryTransaction
                     }
                     _ => {
                         error!("Custom instruction error: {}", err);
-                        log_error(&progress_bar, &err.to_string(), true);
-                        TransactionErrorResult::PropagateError(ClientError {
+                        log_error(progress_bar, &err.to_string(), true);
+                        TransactionErrorResult::PropagateError(Box::new(ClientError {
                             request: None,
                             kind: ClientErrorKind::Custom(err.to_string()),
-                        })
+                        }))
                     }
                 },
 
                 // Non custom instruction error, return
                 _ => {
                     error!("Non-custom instruction error: {}", err);
-                    log_error(&progress_bar, &err.to_string(), true);
-                    TransactionErrorResult::PropagateError(ClientError {
+                    log_error(progress_bar, &err.to_string(), true);
+                    TransactionErrorResult::PropagateError(Box::new(ClientError {
                         request: None,
                         kind: ClientErrorKind::Custom(err.to_string()),
-                    })
+                    }))
                 }
             }
         }
@@ -304,31 +496,19 @@ fn handle_transaction_error(
         // Non instruction error, return
         _ => {
             error!("Non-instruction error: {}", err);
-            log_error(&progress_bar, &err.to_string(), true);
-            TransactionErrorResult::PropagateError(ClientError {
+            log_error(progress_bar, &err.to_string(), true);
+            TransactionErrorResult::PropagateError(Box::new(ClientError {
                 request: None,
                 kind: ClientErrorKind::Custom(err.to_string()),
-            })
+            }))
         }
     }
 }
 
-fn handle_confirmation_status(
-    confirmation: TransactionConfirmationStatus,
-    sig: Signature,
-    progress_bar: &ProgressBar,
-) -> Option<Signature> {
-    debug!("Transaction confirmation status: {:?}", confirmation);
-    match confirmation {
-        TransactionConfirmationStatus::Processed => {
-            debug!("Transaction processed but not confirmed");
-            None // Continue checking
-        }
-        TransactionConfirmationStatus::Confirmed | TransactionConfirmationStatus::Finalized => {
-            debug!("Transaction confirmed/finalized");
-            progress_bar.finish_with_message(format!("{} {}", "OK".bold().green(), sig));
-            Some(sig) // Done, return the signature
-        }
+fn custom_error(err: impl ToString) -> ClientError {
+    ClientError {
+        request: None,
+        kind: ClientErrorKind::Custom(err.to_string()),
     }
 }
 
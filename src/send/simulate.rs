@@ -0,0 +1,91 @@
+use std::time::Duration;
+
+use log::{debug, warn};
+use solana_client::client_error::{ClientError, ClientErrorKind, Result as ClientResult};
+use solana_client::rpc_config::RpcSimulateTransactionConfig;
+use solana_sdk::compute_budget::ComputeBudgetInstruction;
+use solana_sdk::instruction::Instruction;
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::transaction::Transaction;
+use solana_transaction_status::UiTransactionEncoding;
+
+use crate::Miner;
+
+/// The maximum compute units a transaction may request.
+pub const MAX_COMPUTE_UNIT_LIMIT: u32 = 1_400_000;
+
+const SIMULATION_RETRIES: usize = 4;
+const SIMULATION_DELAY: u64 = 500;
+
+impl Miner {
+    /// Simulates the instructions with the maximum compute unit limit and returns
+    /// the compute units they consumed.
+    pub async fn simulate_compute_units(
+        &self,
+        ixs: &[Instruction],
+        payer: &Pubkey,
+        priority_fee: u64,
+    ) -> ClientResult<u64> {
+        // Mirror the compute budget instructions of the final transaction
+        let mut sim_ixs = vec![
+            ComputeBudgetInstruction::set_compute_unit_limit(MAX_COMPUTE_UNIT_LIMIT),
+            ComputeBudgetInstruction::set_compute_unit_price(priority_fee),
+        ];
+        sim_ixs.extend_from_slice(ixs);
+        let tx = Transaction::new_with_payer(&sim_ixs, Some(payer));
+        let config = RpcSimulateTransactionConfig {
+            sig_verify: false,
+            replace_recent_blockhash: true,
+            commitment: Some(self.rpc_client.commitment()),
+            encoding: Some(UiTransactionEncoding::Base64),
+            ..Default::default()
+        };
+
+        let mut attempts = 0;
+        loop {
+            match self
+                .rpc_client
+                .simulate_transaction_with_config(&tx, config.clone())
+                .await
+            {
+                Ok(response) => {
+                    let result = response.value;
+                    if let Some(err) = result.err {
+                        let logs = result.logs.unwrap_or_default();
+                        debug!("Simulation logs: {:?}", logs);
+                        return Err(ClientError {
+                            request: None,
+                            kind: ClientErrorKind::Custom(format!(
+                                "Simulation failed: {}\n  {}",
+                                err,
+                                logs.join("\n  ")
+                            )),
+                        });
+                    }
+                    return result.units_consumed.ok_or_else(|| ClientError {
+                        request: None,
+                        kind: ClientErrorKind::Custom(
+                            "Simulation did not report compute units consumed".into(),
+                        ),
+                    });
+                }
+                Err(err) => {
+                    attempts += 1;
+                    if attempts >= SIMULATION_RETRIES {
+                        return Err(err);
+                    }
+                    warn!("Failed to simulate transaction: {}. Retrying...", err);
+                    tokio::time::sleep(Duration::from_millis(SIMULATION_DELAY)).await;
+                }
+            }
+        }
+    }
+}
+
+/// Adds a safety margin, in percent, to the simulated compute units.
+pub fn compute_unit_limit(units_consumed: u64, margin_percent: u64) -> u32 {
+    units_consumed
+        .saturating_mul(100u64.saturating_add(margin_percent))
+        .div_ceil(100)
+        .min(MAX_COMPUTE_UNIT_LIMIT as u64) as u32
+}
@@ -0,0 +1,174 @@
+use std::{fmt, fs, str::FromStr};
+
+use anyhow::{Result, anyhow, bail};
+use core_affinity::CoreId;
+use log::warn;
+
+/// The cores to hash on, as given to `--cores`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum CoreSelection {
+    /// The first `n` available cores.
+    Count(usize),
+    /// Every available core.
+    All,
+    /// Explicit core ids, such as `0-7,16-23`.
+    List(Vec<usize>),
+}
+
+impl FromStr for CoreSelection {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let value = value.trim();
+        if value.eq_ignore_ascii_case("all") {
+            return Ok(Self::All);
+        }
+        if value.contains([',', '-']) {
+            return parse_core_list(value).map(Self::List);
+        }
+        match value.parse::<usize>() {
+            Ok(0) => Err("The core count must be at least 1".to_string()),
+            Ok(count) => Ok(Self::Count(count)),
+            Err(_) => Err(format!(
+                "Invalid cores \"{}\": expected a count, ALL, or a list like 0-7,16-23",
+                value
+            )),
+        }
+    }
+}
+
+impl fmt::Display for CoreSelection {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Count(count) => write!(f, "{}", count),
+            Self::All => write!(f, "ALL"),
+            Self::List(ids) => {
+                // Consecutive ids are written as ranges, so a single core still
+                // reads back as a list rather than a count
+                let mut ranges: Vec<(usize, usize)> = vec![];
+                for id in ids {
+                    match ranges.last_mut() {
+                        Some((_, last)) if *last + 1 == *id => *last = *id,
+                        _ => ranges.push((*id, *id)),
+                    }
+                }
+                let single = ranges.len() == 1;
+                let ranges = ranges
+                    .iter()
+                    .map(|(first, last)| {
+                        if first == last && !single {
+                            first.to_string()
+                        } else {
+                            format!("{}-{}", first, last)
+                        }
+                    })
+                    .collect::<Vec<_>>();
+                write!(f, "{}", ranges.join(","))
+            }
+        }
+    }
+}
+
+/// Resolves a core selection against the cores this process may run on. With
+/// `skip_smt`, only the first hardware thread of each physical core is used.
+pub fn select_cores(selection: &CoreSelection, skip_smt: bool) -> Result<Vec<CoreId>> {
+    // Core ids may be sparse or start above zero, e.g. inside a cpuset
+    let mut available = core_affinity::get_core_ids()
+        .unwrap_or_else(|| (0..num_cpus::get()).map(|id| CoreId { id }).collect());
+    if skip_smt {
+        match physical_cores(&available) {
+            Some(physical) => available = physical,
+            None => warn!("Cannot detect SMT siblings on this system, using every core"),
+        }
+    }
+    let available_ids = || {
+        let ids = available.iter().map(|core| core.id.to_string());
+        ids.collect::<Vec<_>>().join(",")
+    };
+    let core_ids = match selection {
+        CoreSelection::All => available.clone(),
+        CoreSelection::Count(count) => {
+            if *count > available.len() {
+                bail!(
+                    "Requested cores {} is greater than available cores {}",
+                    count,
+                    available.len()
+                );
+            }
+            available[..*count].to_vec()
+        }
+        CoreSelection::List(ids) => ids
+            .iter()
+            .map(|id| {
+                available
+                    .iter()
+                    .find(|core| core.id == *id)
+                    .copied()
+                    .ok_or_else(|| {
+                        anyhow!(
+                            "Core {} is not available, choose from {}",
+                            id,
+                            available_ids()
+                        )
+                    })
+            })
+            .collect::<Result<_>>()?,
+    };
+    if core_ids.is_empty() {
+        bail!("No cores available to hash on");
+    }
+    Ok(core_ids)
+}
+
+/// Parses a list of core ids and ranges, such as `0-7,16-23`.
+fn parse_core_list(value: &str) -> Result<Vec<usize>, String> {
+    let mut ids = vec![];
+    for part in value
+        .split(',')
+        .map(str::trim)
+        .filter(|part| !part.is_empty())
+    {
+        let parse = |id: &str| {
+            id.trim()
+                .parse::<usize>()
+                .map_err(|_| format!("Invalid core id \"{}\"", id.trim()))
+        };
+        let (first, last) = match part.split_once('-') {
+            Some((first, last)) => (parse(first)?, parse(last)?),
+            None => (parse(part)?, parse(part)?),
+        };
+        if first > last {
+            return Err(format!("Invalid core range {}", part));
+        }
+        for id in first..=last {
+            if !ids.contains(&id) {
+                ids.push(id);
+            }
+        }
+    }
+    if ids.is_empty() {
+        return Err("The core list is empty".to_string());
+    }
+    Ok(ids)
+}
+
+/// The first of `cores` on each physical core, read from the Linux CPU topology.
+/// Returns `None` if the topology is not available.
+fn physical_cores(cores: &[CoreId]) -> Option<Vec<CoreId>> {
+    let mut physical = vec![];
+    for core in cores {
+        let path = format!(
+            "/sys/devices/system/cpu/cpu{}/topology/thread_siblings_list",
+            core.id
+        );
+        let siblings = parse_core_list(fs::read_to_string(path).ok()?.trim()).ok()?;
+        let first_sibling = siblings
+            .into_iter()
+            .filter(|id| cores.iter().any(|core| core.id == *id))
+            .min();
+        if first_sibling == Some(core.id) {
+            physical.push(*core);
+        }
+    }
+    Some(physical)
+}
@@ -0,0 +1,364 @@
+use std::{
+    sync::{
+        Arc,
+        atomic::{AtomicU32, AtomicU64, Ordering},
+        mpsc::{Receiver, Sender, channel},
+    },
+    thread,
+    time::{Duration, Instant},
+};
+
+use anyhow::{Result, bail};
+use colored::Colorize;
+use core_affinity::CoreId;
+use drillx::{Hash, Solution, equix};
+use indicatif::ProgressBar;
+use solana_rpc_client::spinner;
+use tokio::{
+    sync::mpsc::{UnboundedSender, unbounded_channel},
+    time::interval,
+};
+
+use super::format_duration;
+
+/// How often the progress bar is refreshed while hashing.
+const PROGRESS_INTERVAL: Duration = Duration::from_millis(250);
+
+/// A long lived pool of hashing threads, one for each core. The threads keep their
+/// solver memory between rounds and take new work over a channel.
+pub struct HashPool {
+    workers: Vec<Sender<WorkerJob>>,
+    /// The core id of each worker and the hashes it has computed so far.
+    hash_counts: Vec<(usize, Arc<AtomicU64>)>,
+    /// Bumped by every round. Workers stop as soon as it moves past their round.
+    generation: Arc<AtomicU64>,
+}
+
+/// The drillx function the workers hash with.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum HashFunction {
+    /// `hash_with_memory`, one hash per nonce.
+    Single,
+    /// `hashes_with_memory`, every equihash solution of a nonce. Collecting uses it.
+    Multiple,
+}
+
+impl HashFunction {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Single => "hash_with_memory",
+            Self::Multiple => "hashes_with_memory",
+        }
+    }
+}
+
+/// A hashing round.
+pub struct HashJob {
+    pub challenge: [u8; 32],
+    pub hash_function: HashFunction,
+    /// The nonce each worker starts at, in the order of the pool's cores.
+    pub nonce_indices: Vec<u64>,
+    /// How long to hash before settling for the best solution.
+    pub cutoff: Duration,
+    /// Keep hashing past the cutoff until a solution reaches this difficulty.
+    pub min_difficulty: u32,
+    /// Stop before the cutoff once a solution reaches this difficulty, but not
+    /// before `min_elapsed` has passed.
+    pub target_difficulty: Option<u32>,
+    pub min_elapsed: Duration,
+    /// Receives each new best solution that reaches the minimum difficulty.
+    pub solutions: Option<UnboundedSender<Solution>>,
+}
+
+/// The outcome of a hashing round.
+pub struct HashRound {
+    pub solution: Solution,
+    pub best_difficulty: u32,
+    /// The core id and the number of hashes each worker computed.
+    pub core_hashes: Vec<(usize, u64)>,
+    pub elapsed: Duration,
+}
+
+struct WorkerJob {
+    generation: u64,
+    challenge: [u8; 32],
+    hash_function: HashFunction,
+    first_nonce: u64,
+    started_at: Instant,
+    cutoff: Duration,
+    min_difficulty: u32,
+    target_difficulty: Option<u32>,
+    min_elapsed: Duration,
+    best_difficulty: Arc<AtomicU32>,
+    solutions: Option<UnboundedSender<Solution>>,
+    results: UnboundedSender<WorkerResult>,
+}
+
+struct WorkerResult {
+    core: usize,
+    nonce: u64,
+    difficulty: u32,
+    hash: Hash,
+    hashes: u64,
+}
+
+impl HashPool {
+    /// Spawns one worker for each of `core_ids`, pinned to it unless `pinned` is
+    /// false, in which case the OS schedules the workers.
+    pub fn new(core_ids: &[CoreId], pinned: bool) -> Self {
+        let generation = Arc::new(AtomicU64::new(0));
+        let mut workers = Vec::with_capacity(core_ids.len());
+        let mut hash_counts = Vec::with_capacity(core_ids.len());
+        for core in core_ids {
+            let (jobs, receiver) = channel();
+            let worker = Worker {
+                core: *core,
+                pinned,
+                hashes: Arc::new(AtomicU64::new(0)),
+                generation: generation.clone(),
+            };
+            hash_counts.push((core.id, worker.hashes.clone()));
+            thread::spawn(move || run_worker(worker, receiver));
+            workers.push(jobs);
+        }
+        Self {
+            workers,
+            hash_counts,
+            generation,
+        }
+    }
+
+    pub fn cores(&self) -> usize {
+        self.workers.len()
+    }
+
+    /// The core id of each worker and the hashes it has computed since the pool
+    /// started, updated while a round is in progress.
+    pub fn hash_counts(&self) -> Vec<(usize, u64)> {
+        self.hash_counts
+            .iter()
+            .map(|(core, hashes)| (*core, hashes.load(Ordering::Relaxed)))
+            .collect()
+    }
+
+    /// Stops the round in progress. Its workers report what they found so far.
+    pub fn interrupt(&self) {
+        self.generation.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Hashes a round on every worker, interrupting the round in progress.
+    /// Dropping the returned future interrupts the round too.
+    pub async fn hash(&self, job: HashJob, show_progress: bool) -> Result<HashRound> {
+        if job.nonce_indices.len() != self.workers.len() {
+            bail!(
+                "Expected {} nonce indices, got {}",
+                self.workers.len(),
+                job.nonce_indices.len()
+            );
+        }
+        let generation = self.generation.fetch_add(1, Ordering::Relaxed) + 1;
+        let _interrupt = InterruptOnDrop {
+            generation: &self.generation,
+            round: generation,
+        };
+        let started_at = Instant::now();
+        let best_difficulty = Arc::new(AtomicU32::new(0));
+        let (results, mut received) = unbounded_channel();
+        for (worker, first_nonce) in self.workers.iter().zip(&job.nonce_indices) {
+            worker.send(WorkerJob {
+                generation,
+                challenge: job.challenge,
+                hash_function: job.hash_function,
+                first_nonce: *first_nonce,
+                started_at,
+                cutoff: job.cutoff,
+                min_difficulty: job.min_difficulty,
+                target_difficulty: job.target_difficulty,
+                min_elapsed: job.min_elapsed,
+                best_difficulty: best_difficulty.clone(),
+                solutions: job.solutions.clone(),
+                results: results.clone(),
+            })?;
+        }
+        drop(results);
+
+        // Wait for every worker, showing the best difficulty so far
+        let progress_bar = if show_progress {
+            spinner::new_progress_bar()
+        } else {
+            ProgressBar::hidden()
+        };
+        let mut ticks = interval(PROGRESS_INTERVAL);
+        let mut worker_results = Vec::with_capacity(self.workers.len());
+        loop {
+            tokio::select! {
+                result = received.recv() => match result {
+                    Some(result) => worker_results.push(result),
+                    None => break,
+                },
+                _ = ticks.tick() => {
+                    let best = best_difficulty.load(Ordering::Relaxed);
+                    let remaining = job.cutoff.saturating_sub(started_at.elapsed());
+                    let mut message = format!("Collecting...\n  Best score: {}", best);
+                    if let Some(target) = job.target_difficulty {
+                        message.push_str(&format!("\n  Target score: {}", target));
+                    }
+                    if !remaining.is_zero() {
+                        message.push_str(&format!(
+                            "\n  Time remaining: {}",
+                            format_duration(remaining.as_secs() as u32)
+                        ));
+                    }
+                    progress_bar.set_message(message);
+                }
+            }
+        }
+        progress_bar.finish_and_clear();
+        if worker_results.len() != self.workers.len() {
+            bail!(
+                "{} of {} hashing workers stopped",
+                self.workers.len() - worker_results.len(),
+                self.workers.len()
+            );
+        }
+
+        let core_hashes = worker_results
+            .iter()
+            .map(|result| (result.core, result.hashes))
+            .collect();
+        let best = worker_results
+            .into_iter()
+            .max_by_key(|result| result.difficulty)
+            .expect("Hash pool has no workers");
+        Ok(HashRound {
+            solution: Solution::new(best.hash.d, best.nonce.to_le_bytes()),
+            best_difficulty: best.difficulty,
+            core_hashes,
+            elapsed: started_at.elapsed(),
+        })
+    }
+}
+
+impl Drop for HashPool {
+    fn drop(&mut self) {
+        // The workers exit once their channels close
+        self.interrupt();
+    }
+}
+
+/// Stops the workers if the round future is dropped before they finish.
+struct InterruptOnDrop<'a> {
+    generation: &'a AtomicU64,
+    round: u64,
+}
+
+impl Drop for InterruptOnDrop<'_> {
+    fn drop(&mut self) {
+        let _ = self.generation.compare_exchange(
+            self.round,
+            self.round + 1,
+            Ordering::Relaxed,
+            Ordering::Relaxed,
+        );
+    }
+}
+
+struct Worker {
+    core: CoreId,
+    pinned: bool,
+    hashes: Arc<AtomicU64>,
+    generation: Arc<AtomicU64>,
+}
+
+fn run_worker(worker: Worker, jobs: Receiver<WorkerJob>) {
+    let Worker {
+        core,
+        pinned,
+        hashes,
+        generation,
+    } = worker;
+
+    // Pin to core, it might not be supported on modern macOS/Apple Silicon
+    if pinned {
+        let _ = core_affinity::set_for_current(core);
+    }
+    let mut memory = equix::SolverMemory::new();
+
+    // Exits once the pool is dropped
+    while let Ok(job) = jobs.recv() {
+        let mut nonce = job.first_nonce;
+        let mut best_nonce = nonce;
+        let mut best_difficulty = 0;
+        let mut best_hash = Hash::default();
+        let mut round_hashes = 0;
+        loop {
+            // Get hashes
+            let hxs = match job.hash_function {
+                HashFunction::Single => {
+                    drillx::hash_with_memory(&mut memory, &job.challenge, &nonce.to_le_bytes())
+                        .into_iter()
+                        .collect()
+                }
+                HashFunction::Multiple => {
+                    drillx::hashes_with_memory(&mut memory, &job.challenge, &nonce.to_le_bytes())
+                }
+            };
+            round_hashes += hxs.len() as u64;
+            hashes.fetch_add(hxs.len() as u64, Ordering::Relaxed);
+
+            // Look for best difficulty score in all hashes
+            for hx in hxs {
+                let difficulty = hx.difficulty();
+                if difficulty.gt(&best_difficulty) {
+                    best_nonce = nonce;
+                    best_difficulty = difficulty;
+                    best_hash = hx;
+                    let global_best = job.best_difficulty.fetch_max(difficulty, Ordering::Relaxed);
+
+                    // Continuously upload best solution to pool
+                    if difficulty.gt(&global_best)
+                        && difficulty.ge(&job.min_difficulty)
+                        && let Some(ref ch) = job.solutions
+                    {
+                        let solution = Solution::new(best_hash.d, nonce.to_le_bytes());
+                        if let Err(err) = ch.send(solution) {
+                            println!("{} {:?}", "ERROR".bold().red(), err);
+                        }
+                    }
+                }
+            }
+
+            // Exit if interrupted, if time has elapsed and min difficulty has been met,
+            // or as soon as any worker reaches the target difficulty
+            if nonce.is_multiple_of(100) {
+                if generation.load(Ordering::Relaxed) != job.generation {
+                    break;
+                }
+                let elapsed = job.started_at.elapsed();
+                let global_best = job.best_difficulty.load(Ordering::Relaxed);
+                if elapsed >= job.cutoff && global_best.ge(&job.min_difficulty) {
+                    break;
+                }
+                if elapsed >= job.min_elapsed
+                    && job
+                        .target_difficulty
+                        .is_some_and(|target| global_best.ge(&target))
+                {
+                    break;
+                }
+            }
+
+            // Increment nonce
+            nonce += 1;
+        }
+
+        // Return the best nonce and hash count
+        let _ = job.results.send(WorkerResult {
+            core: core.id,
+            nonce: best_nonce,
+            difficulty: best_difficulty,
+            hash: best_hash,
+            hashes: round_hashes,
+        });
+    }
+}
@@ -0,0 +1,244 @@
+use std::{
+    collections::HashMap,
+    fs::{File, OpenOptions, create_dir_all},
+    io::{BufRead, BufReader, ErrorKind, Write},
+    path::{Path, PathBuf},
+};
+
+use anyhow::{Context, Result};
+use log::warn;
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum MiningStatus {
+    /// Sent and not yet found on-chain.
+    Sent,
+    Confirmed,
+    Failed,
+    /// Confirmed when sent, but the transaction or its mine event could not be fetched.
+    Unknown,
+}
+
+/// A submitted mine transaction. Rewards are in grains, timestamps in unix seconds.
+///
+/// A record is written when its transaction is sent and again once its outcome is
+/// known. The last record of a signature is the one that counts.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct MiningRecord {
+    pub signature: Option<String>,
+    /// The proof authority the transaction mined for. Missing in older records.
+    #[serde(default)]
+    pub authority: Option<String>,
+    pub slot: Option<u64>,
+    pub timestamp: i64,
+    pub difficulty: Option<u64>,
+    pub base_reward: u64,
+    pub boost_reward: u64,
+    pub total_reward: u64,
+    pub timing: Option<i64>,
+    pub status: MiningStatus,
+}
+
+impl MiningRecord {
+    pub fn sent(authority: String, signature: String, timestamp: i64) -> Self {
+        Self {
+            signature: Some(signature),
+            authority: Some(authority),
+            slot: None,
+            timestamp,
+            difficulty: None,
+            base_reward: 0,
+            boost_reward: 0,
+            total_reward: 0,
+            timing: None,
+            status: MiningStatus::Sent,
+        }
+    }
+
+    /// A transaction that did not land. `signature` is that of the last attempt, if
+    /// it got as far as being sent.
+    pub fn failed(authority: String, signature: Option<String>, timestamp: i64) -> Self {
+        Self {
+            signature,
+            authority: Some(authority),
+            slot: None,
+            timestamp,
+            difficulty: None,
+            base_reward: 0,
+            boost_reward: 0,
+            total_reward: 0,
+            timing: None,
+            status: MiningStatus::Failed,
+        }
+    }
+}
+
+/// An append-only JSON lines log of mining records.
+pub struct MiningHistory {
+    path: PathBuf,
+}
+
+impl MiningHistory {
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self { path: path.into() }
+    }
+
+    /// `~/.config/bitz/history.jsonl`
+    pub fn default_path() -> PathBuf {
+        let home = std::env::var_os("HOME")
+            .or_else(|| std::env::var_os("USERPROFILE"))
+            .unwrap_or_else(|| ".".into());
+        Path::new(&home)
+            .join(".config")
+            .join("bitz")
+            .join("history.jsonl")
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    pub fn append(&self, record: &MiningRecord) -> Result<()> {
+        if let Some(dir) = self.path.parent() {
+            create_dir_all(dir)?;
+        }
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)
+            .with_context(|| format!("Failed to open history file {}", self.path.display()))?;
+        let mut line = serde_json::to_string(record)?;
+        line.push('\n');
+        file.write_all(line.as_bytes())?;
+        Ok(())
+    }
+
+    /// Reads the latest record of each transaction with a timestamp in `from..to`,
+    /// oldest first.
+    pub fn read_range(&self, from: Option<i64>, to: Option<i64>) -> Result<Vec<MiningRecord>> {
+        let file = match File::open(&self.path) {
+            Ok(file) => file,
+            Err(err) if err.kind() == ErrorKind::NotFound => return Ok(vec![]),
+            Err(err) => {
+                return Err(err).with_context(|| {
+                    format!("Failed to open history file {}", self.path.display())
+                });
+            }
+        };
+        let mut records: Vec<MiningRecord> = vec![];
+        let mut positions = HashMap::new();
+        for (index, line) in BufReader::new(file).lines().enumerate() {
+            let line = line?;
+            if line.trim().is_empty() {
+                continue;
+            }
+            // Skip lines cut short by an interrupted write
+            let record = match serde_json::from_str::<MiningRecord>(&line) {
+                Ok(record) => record,
+                Err(err) => {
+                    warn!("Skipping history line {}: {}", index + 1, err);
+                    continue;
+                }
+            };
+            // A later record of the same transaction replaces the earlier one
+            match record
+                .signature
+                .clone()
+                .and_then(|signature| positions.get(&signature).copied())
+            {
+                Some(position) => records[position] = record,
+                None => {
+                    if let Some(signature) = &record.signature {
+                        positions.insert(signature.clone(), records.len());
+                    }
+                    records.push(record);
+                }
+            }
+        }
+        records.retain(|record| {
+            from.is_none_or(|from| record.timestamp >= from)
+                && to.is_none_or(|to| record.timestamp < to)
+        });
+        records.sort_by_key(|record| record.timestamp);
+        Ok(records)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_history(name: &str) -> MiningHistory {
+        let path = std::env::temp_dir().join(format!(
+            "bitz-history-{}-{}.jsonl",
+            name,
+            std::process::id()
+        ));
+        let _ = std::fs::remove_file(&path);
+        MiningHistory::new(path)
+    }
+
+    #[test]
+    fn later_records_replace_earlier_ones() {
+        let history = temp_history("replace");
+        let authority = "authority".to_string();
+        let sent = MiningRecord::sent(authority.clone(), "sig-1".to_string(), 100);
+        history.append(&sent).unwrap();
+        history
+            .append(&MiningRecord::sent(
+                authority.clone(),
+                "sig-2".to_string(),
+                160,
+            ))
+            .unwrap();
+        history
+            .append(&MiningRecord::failed(authority.clone(), None, 200))
+            .unwrap();
+        history
+            .append(&MiningRecord {
+                slot: Some(7),
+                timestamp: 102,
+                difficulty: Some(20),
+                total_reward: 5,
+                status: MiningStatus::Confirmed,
+                ..sent
+            })
+            .unwrap();
+
+        let records = history.read_range(None, None).unwrap();
+        let statuses = records
+            .iter()
+            .map(|record| (record.signature.as_deref(), record.status))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            statuses,
+            [
+                (Some("sig-1"), MiningStatus::Confirmed),
+                (Some("sig-2"), MiningStatus::Sent),
+                (None, MiningStatus::Failed),
+            ]
+        );
+        assert_eq!(records[0].timestamp, 102);
+        assert_eq!(records[0].authority.as_deref(), Some("authority"));
+
+        let records = history.read_range(Some(150), Some(200)).unwrap();
+        assert_eq!(records.len(), 1);
+        assert_eq!(records[0].signature.as_deref(), Some("sig-2"));
+        std::fs::remove_file(history.path()).unwrap();
+    }
+
+    #[test]
+    fn reads_records_without_an_authority() {
+        let history = temp_history("legacy");
+        std::fs::write(
+            history.path(),
+            "{\"signature\":\"sig\",\"slot\":1,\"timestamp\":5,\"difficulty\":18,\"base_reward\":1,\"boost_reward\":0,\"total_reward\":1,\"timing\":2,\"status\":\"confirmed\"}\n{\"cut short",
+        )
+        .unwrap();
+        let records = history.read_range(None, None).unwrap();
+        assert_eq!(records.len(), 1);
+        assert_eq!(records[0].authority, None);
+        std::fs::remove_file(history.path()).unwrap();
+    }
+}
@@ -1,8 +1,14 @@
-mod find_hash_parallel;
+mod cores;
+mod hash_pool;
+mod history;
 mod io;
 mod rpc;
+mod subscription;
 mod table;
+pub use cores::*;
+pub use hash_pool::*;
+pub use history::*;
 pub use io::*;
 pub use rpc::*;
+pub use subscription::*;
 pub use table::*;
-pub use find_hash_parallel::*;
@@ -1,21 +1,29 @@
 #![allow(dead_code)]
 use std::time::Duration;
 
-use anyhow::Result;
+use anyhow::{Result, bail};
 use eore_api::{
     consts::CONFIG_ADDRESS,
     state::{Config, Proof, proof_pda},
 };
-use eore_boost_api::state::Boost;
+use eore_boost_api::state::{Boost, Stake};
+use mpl_token_metadata::accounts::Metadata;
 use serde::Deserialize;
+use solana_account_decoder::UiAccountEncoding;
 use solana_client::{
     client_error::{ClientError, ClientErrorKind},
     nonblocking::rpc_client::RpcClient,
+    rpc_config::{RpcAccountInfoConfig, RpcProgramAccountsConfig},
+    rpc_filter::{Memcmp, RpcFilterType},
+    rpc_request::MAX_MULTIPLE_ACCOUNTS,
 };
-use solana_program::{pubkey::Pubkey, sysvar};
+use solana_program::{nonce, pubkey::Pubkey, system_program, sysvar};
+use solana_sdk::account::Account;
 use solana_sdk::{clock::Clock, hash::Hash, program_pack::Pack};
 use spl_token::state::Mint;
-use steel::AccountDeserialize;
+use steel::{AccountDeserialize, Discriminator};
+
+use crate::constants::NONCE_SEED;
 
 pub const BLOCKHASH_QUERY_RETRIES: usize = 5;
 pub const BLOCKHASH_QUERY_DELAY: u64 = 500;
@@ -24,6 +32,31 @@ pub enum ComputeBudget {
     Fixed(u32),
 }
 
+/// Where a transaction gets its blockhash from.
+#[derive(Clone, Copy, Default)]
+pub enum BlockhashSource {
+    /// A recent blockhash, which expires after about a minute.
+    #[default]
+    Recent,
+    /// The value stored in a durable nonce account, advanced by the transaction.
+    DurableNonce(Pubkey),
+}
+
+/// The durable nonce account of an authority, derived from its address.
+pub fn nonce_address(authority: Pubkey) -> Pubkey {
+    Pubkey::create_with_seed(&authority, NONCE_SEED, &system_program::ID)
+        .expect("Failed to derive nonce address")
+}
+
+pub async fn get_nonce(client: &RpcClient, address: Pubkey) -> Result<nonce::state::Data> {
+    let data = client.get_account_data(&address).await?;
+    let versions = bincode::deserialize::<nonce::state::Versions>(&data)?;
+    match versions.state() {
+        nonce::State::Initialized(data) => Ok(data.clone()),
+        nonce::State::Uninitialized => bail!("Nonce account {} is not initialized", address),
+    }
+}
+
 pub async fn get_config(client: &RpcClient) -> Result<Config> {
     let data = client.get_account_data(&CONFIG_ADDRESS).await?;
     let config = Config::try_from_bytes(&data)?;
@@ -36,6 +69,37 @@ pub async fn get_mint(client: &RpcClient, address: Pubkey) -> Result<Mint> {
     Ok(mint)
 }
 
+/// Fetches several mints at once. Missing or invalid mints are `None`.
+pub async fn get_mints(client: &RpcClient, addresses: &[Pubkey]) -> Result<Vec<Option<Mint>>> {
+    let accounts = get_multiple_accounts_batched(client, addresses).await?;
+    Ok(accounts
+        .into_iter()
+        .map(|account| account.and_then(|account| Mint::unpack(&account.data).ok()))
+        .collect())
+}
+
+/// Reads the token symbols of several mints from their Metaplex metadata accounts.
+/// Mints without metadata are `None`.
+pub async fn get_token_symbols(
+    client: &RpcClient,
+    mints: &[Pubkey],
+) -> Result<Vec<Option<String>>> {
+    let addresses = mints
+        .iter()
+        .map(|mint| Metadata::find_pda(mint).0)
+        .collect::<Vec<_>>();
+    let accounts = get_multiple_accounts_batched(client, &addresses).await?;
+    Ok(accounts
+        .into_iter()
+        .map(|account| account.and_then(|account| parse_token_symbol(&account.data).ok()))
+        .collect())
+}
+
+fn parse_token_symbol(data: &[u8]) -> Result<String> {
+    let metadata = Metadata::from_bytes(data)?;
+    Ok(metadata.symbol.trim_end_matches('\0').trim().to_string())
+}
+
 pub async fn get_proof(client: &RpcClient, address: Pubkey) -> Result<Proof> {
     let data = client.get_account_data(&address).await?;
     let proof = Proof::try_from_bytes(&data)?;
@@ -48,22 +112,6 @@ pub async fn get_proof_with_authority(client: &RpcClient, authority: Pubkey) ->
     let proof = Proof::try_from_bytes(&data)?;
     Ok(*proof)
 }
-//todo: reactor
-pub async fn get_updated_proof_with_authority(
-    client: &RpcClient,
-    authority: Pubkey,
-    lash_hash_at: i64,
-) -> Result<Proof, anyhow::Error> {
-    loop {
-        if let Ok(proof) = get_proof_with_authority(client, authority).await {
-            if proof.last_hash_at.gt(&lash_hash_at) {
-                return Ok(proof);
-            }
-        }
-        tokio::time::sleep(Duration::from_millis(1_000)).await;
-    }
-}
-
 pub async fn get_clock(client: &RpcClient) -> Result<Clock, anyhow::Error> {
     retry(|| async {
         let data = client.get_account_data(&sysvar::clock::ID).await?;
@@ -80,22 +128,128 @@ pub async fn get_boost_config(client: &RpcClient) -> eore_boost_api::state::Conf
     *eore_boost_api::state::Config::try_from_bytes(&data).expect("Failed to parse config account")
 }
 
+/// The proof of the boost config, whose balance holds staking rewards that boosts
+/// have not collected yet.
+pub async fn get_boost_proof(client: &RpcClient) -> Result<Proof> {
+    get_proof(client, proof_pda(eore_boost_api::state::config_pda().0).0).await
+}
+
 pub async fn get_boost(client: &RpcClient, address: Pubkey) -> Result<Boost, anyhow::Error> {
     let data = client.get_account_data(&address).await?;
     Ok(*Boost::try_from_bytes(&data).expect("Failed to parse boost account"))
 }
 
+pub async fn get_stake(client: &RpcClient, address: Pubkey) -> Result<Stake> {
+    let data = client.get_account_data(&address).await?;
+    let stake = Stake::try_from_bytes(&data)?;
+    Ok(*stake)
+}
+
+/// Fetches any number of accounts, in batches of the most one request may ask for.
+pub async fn get_multiple_accounts_batched(
+    client: &RpcClient,
+    addresses: &[Pubkey],
+) -> Result<Vec<Option<Account>>> {
+    let mut accounts = Vec::with_capacity(addresses.len());
+    for batch in addresses.chunks(MAX_MULTIPLE_ACCOUNTS) {
+        accounts.extend(client.get_multiple_accounts(batch).await?);
+    }
+    Ok(accounts)
+}
+
+/// Fetches every boost account owned by the boost program.
+pub async fn get_boosts(client: &RpcClient) -> Result<Vec<(Pubkey, Boost)>> {
+    let accounts = client
+        .get_program_accounts_with_config(
+            &eore_boost_api::ID,
+            RpcProgramAccountsConfig {
+                filters: Some(vec![RpcFilterType::Memcmp(Memcmp::new_raw_bytes(
+                    0,
+                    vec![Boost::discriminator()],
+                ))]),
+                account_config: RpcAccountInfoConfig {
+                    encoding: Some(UiAccountEncoding::Base64),
+                    ..Default::default()
+                },
+                ..Default::default()
+            },
+        )
+        .await?;
+    Ok(accounts
+        .into_iter()
+        .filter_map(|(address, account)| {
+            Boost::try_from_bytes(&account.data)
+                .ok()
+                .map(|boost| (address, *boost))
+        })
+        .collect())
+}
+
+/// Fetches every stake account deposited in the given boost.
+pub async fn get_boost_stake_accounts(
+    client: &RpcClient,
+    boost_address: Pubkey,
+) -> Result<Vec<(Pubkey, Stake)>> {
+    // Stake layout: 8 byte discriminator, authority, balance, boost
+    const BOOST_OFFSET: usize = 8 + 32 + 8;
+    let accounts = client
+        .get_program_accounts_with_config(
+            &eore_boost_api::ID,
+            RpcProgramAccountsConfig {
+                filters: Some(vec![
+                    RpcFilterType::Memcmp(Memcmp::new_raw_bytes(0, vec![Stake::discriminator()])),
+                    RpcFilterType::Memcmp(Memcmp::new_raw_bytes(
+                        BOOST_OFFSET,
+                        boost_address.to_bytes().to_vec(),
+                    )),
+                ]),
+                account_config: RpcAccountInfoConfig {
+                    encoding: Some(UiAccountEncoding::Base64),
+                    ..Default::default()
+                },
+                ..Default::default()
+            },
+        )
+        .await?;
+    Ok(accounts
+        .into_iter()
+        .filter_map(|(address, account)| {
+            Stake::try_from_bytes(&account.data)
+                .ok()
+                .map(|stake| (address, *stake))
+        })
+        .collect())
+}
+
+pub async fn get_pool(client: &RpcClient, address: Pubkey) -> Result<ore_pool_api::state::Pool> {
+    let data = client.get_account_data(&address).await?;
+    let pool = ore_pool_api::state::Pool::try_from_bytes(&data)?;
+    Ok(*pool)
+}
+
+pub async fn get_pool_member_onchain(
+    client: &RpcClient,
+    authority: Pubkey,
+    pool_address: Pubkey,
+) -> Result<ore_pool_api::state::Member> {
+    let address = ore_pool_api::state::member_pda(authority, pool_address).0;
+    let data = client.get_account_data(&address).await?;
+    let member = ore_pool_api::state::Member::try_from_bytes(&data)?;
+    Ok(*member)
+}
+
+/// Returns the latest blockhash and the last block height it is valid for.
 pub async fn get_latest_blockhash_with_retries(
     client: &RpcClient,
 ) -> Result<(Hash, u64), ClientError> {
     let mut attempts = 0;
 
     loop {
-        if let Ok((hash, slot)) = client
+        if let Ok((hash, last_valid_block_height)) = client
             .get_latest_blockhash_with_commitment(client.commitment())
             .await
         {
-            return Ok((hash, slot));
+            return Ok((hash, last_valid_block_height));
         }
 
         // Retry
@@ -0,0 +1,201 @@
+use std::{
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+use anyhow::Result;
+use eore_api::{
+    consts::CONFIG_ADDRESS,
+    state::{Config, Proof, proof_pda},
+};
+use futures::StreamExt;
+use log::{debug, warn};
+use solana_account_decoder::UiAccountEncoding;
+use solana_client::{
+    nonblocking::{pubsub_client::PubsubClient, rpc_client::RpcClient},
+    rpc_config::RpcAccountInfoConfig,
+};
+use solana_program::{pubkey::Pubkey, sysvar};
+use solana_sdk::{clock::Clock, commitment_config::CommitmentConfig};
+use steel::AccountDeserialize;
+use tokio::{sync::watch, task::JoinHandle, time::sleep};
+
+use super::{get_clock, get_config, get_proof_with_authority};
+
+/// Seconds to poll over RPC after the websocket drops, before resubscribing.
+const RESUBSCRIBE_DELAY: u64 = 5;
+/// How long to wait for a proof update before checking over RPC.
+const PROOF_POLL_INTERVAL: Duration = Duration::from_secs(5);
+/// Clock updates older than this are refetched over RPC.
+const MAX_CLOCK_AGE: Duration = Duration::from_secs(5);
+
+/// Keeps the proof, config and clock accounts of a collecting authority up to date
+/// over a PubSub websocket, falling back to RPC polling while it is disconnected.
+pub struct AccountSubscription {
+    rpc_client: Arc<RpcClient>,
+    authority: Pubkey,
+    accounts: Arc<WatchedAccounts>,
+    proof: watch::Receiver<Option<Proof>>,
+    task: JoinHandle<()>,
+}
+
+struct WatchedAccounts {
+    proof: watch::Sender<Option<Proof>>,
+    config: watch::Sender<Option<Config>>,
+    clock: watch::Sender<Option<(Clock, Instant)>>,
+}
+
+impl AccountSubscription {
+    pub async fn new(rpc_client: Arc<RpcClient>, websocket_url: String, authority: Pubkey) -> Self {
+        let accounts = Arc::new(WatchedAccounts {
+            proof: watch::Sender::new(None),
+            config: watch::Sender::new(None),
+            clock: watch::Sender::new(None),
+        });
+        accounts.poll(&rpc_client, authority).await;
+        let proof = accounts.proof.subscribe();
+        let task = tokio::spawn({
+            let rpc_client = rpc_client.clone();
+            let accounts = accounts.clone();
+            async move {
+                loop {
+                    match subscribe(&websocket_url, authority, &accounts).await {
+                        Ok(()) => warn!("Account subscription closed, resubscribing"),
+                        Err(err) => warn!(
+                            "Account subscription to {} failed: {}. Polling over RPC",
+                            websocket_url, err
+                        ),
+                    }
+                    for _ in 0..RESUBSCRIBE_DELAY {
+                        accounts.poll(&rpc_client, authority).await;
+                        sleep(Duration::from_secs(1)).await;
+                    }
+                }
+            }
+        });
+        Self {
+            rpc_client,
+            authority,
+            accounts,
+            proof,
+            task,
+        }
+    }
+
+    /// Waits for a proof whose last hash is newer than `last_hash_at`.
+    pub async fn next_proof(&mut self, last_hash_at: i64) -> Result<Proof> {
+        loop {
+            let updated = |proof: &Option<Proof>| {
+                proof.is_some_and(|proof| proof.last_hash_at.gt(&last_hash_at))
+            };
+            if let Ok(proof) =
+                tokio::time::timeout(PROOF_POLL_INTERVAL, self.proof.wait_for(updated)).await
+            {
+                if let Some(proof) = *proof? {
+                    return Ok(proof);
+                }
+            } else if let Ok(proof) =
+                get_proof_with_authority(&self.rpc_client, self.authority).await
+            {
+                // Quiet socket, the update may have been missed
+                self.accounts.proof.send_replace(Some(proof));
+            }
+        }
+    }
+
+    pub async fn config(&self) -> Result<Config> {
+        let config = *self.accounts.config.borrow();
+        match config {
+            Some(config) => Ok(config),
+            None => get_config(&self.rpc_client).await,
+        }
+    }
+
+    pub async fn clock(&self) -> Result<Clock> {
+        if let Some((clock, received_at)) = self.accounts.clock.borrow().clone()
+            && received_at.elapsed() < MAX_CLOCK_AGE
+        {
+            return Ok(clock);
+        }
+        let clock = get_clock(&self.rpc_client).await?;
+        self.accounts
+            .clock
+            .send_replace(Some((clock.clone(), Instant::now())));
+        Ok(clock)
+    }
+}
+
+impl Drop for AccountSubscription {
+    fn drop(&mut self) {
+        self.task.abort();
+    }
+}
+
+impl WatchedAccounts {
+    async fn poll(&self, rpc_client: &RpcClient, authority: Pubkey) {
+        if let Ok(proof) = get_proof_with_authority(rpc_client, authority).await {
+            self.proof.send_replace(Some(proof));
+        }
+        if let Ok(config) = get_config(rpc_client).await {
+            self.config.send_replace(Some(config));
+        }
+        if let Ok(clock) = get_clock(rpc_client).await {
+            self.clock.send_replace(Some((clock, Instant::now())));
+        }
+    }
+}
+
+/// Streams account updates into `accounts` until the websocket closes.
+async fn subscribe(
+    websocket_url: &str,
+    authority: Pubkey,
+    accounts: &WatchedAccounts,
+) -> Result<()> {
+    let client = PubsubClient::new(websocket_url).await?;
+    let config = RpcAccountInfoConfig {
+        encoding: Some(UiAccountEncoding::Base64),
+        commitment: Some(CommitmentConfig::confirmed()),
+        ..Default::default()
+    };
+    let (mut proofs, _) = client
+        .account_subscribe(&proof_pda(authority).0, Some(config.clone()))
+        .await?;
+    let (mut configs, _) = client
+        .account_subscribe(&CONFIG_ADDRESS, Some(config.clone()))
+        .await?;
+    let (mut clocks, _) = client
+        .account_subscribe(&sysvar::clock::ID, Some(config))
+        .await?;
+    debug!(
+        "Subscribed to proof, config and clock accounts at {}",
+        websocket_url
+    );
+    loop {
+        tokio::select! {
+            update = proofs.next() => {
+                let Some(update) = update else { return Ok(()) };
+                if let Some(data) = update.value.data.decode()
+                    && let Ok(proof) = Proof::try_from_bytes(&data)
+                {
+                    accounts.proof.send_replace(Some(*proof));
+                }
+            }
+            update = configs.next() => {
+                let Some(update) = update else { return Ok(()) };
+                if let Some(data) = update.value.data.decode()
+                    && let Ok(config) = Config::try_from_bytes(&data)
+                {
+                    accounts.config.send_replace(Some(*config));
+                }
+            }
+            update = clocks.next() => {
+                let Some(update) = update else { return Ok(()) };
+                if let Some(data) = update.value.data.decode()
+                    && let Ok(clock) = bincode::deserialize::<Clock>(&data)
+                {
+                    accounts.clock.send_replace(Some((clock, Instant::now())));
+                }
+            }
+        }
+    }
+}
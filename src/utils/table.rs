@@ -1,5 +1,4 @@
 use colored::Colorize;
-use solana_sdk::signature::Signature;
 use tabled::{
     Table, Tabled,
     settings::{
@@ -37,34 +36,24 @@ pub struct SoloCollectingData {
     pub status: String,
 }
 
-impl SoloCollectingData {
-    pub fn fetching(sig: Signature) -> Self {
-        Self {
-            signature: sig.to_string(),
-            block: "–".to_string(),
-            timestamp: "–".to_string(),
-            difficulty: "–".to_string(),
-            base_reward: "–".to_string(),
-            boost_reward: "–".to_string(),
-            total_reward: "–".to_string(),
-            timing: "–".to_string(),
-            status: "Fetching".to_string(),
-        }
-    }
-
-    pub fn failed() -> Self {
-        Self {
-            signature: "–".to_string(),
-            block: "–".to_string(),
-            timestamp: "–".to_string(),
-            difficulty: "–".to_string(),
-            base_reward: "–".to_string(),
-            boost_reward: "–".to_string(),
-            total_reward: "–".to_string(),
-            timing: "–".to_string(),
-            status: "Failed".bold().red().to_string(),
-        }
-    }
+#[derive(Clone, Tabled)]
+pub struct WalletCollectingData {
+    #[tabled(rename = "Wallet")]
+    pub wallet: String,
+    #[tabled(rename = "Rounds")]
+    pub rounds: String,
+    #[tabled(rename = "Failed")]
+    pub failed: String,
+    #[tabled(rename = "Last Score")]
+    pub difficulty: String,
+    #[tabled(rename = "Last Timing")]
+    pub timing: String,
+    #[tabled(rename = "Last Reward")]
+    pub last_reward: String,
+    #[tabled(rename = "Total Reward")]
+    pub total_reward: String,
+    #[tabled(rename = "Status")]
+    pub status: String,
 }
 
 #[derive(Clone, Tabled)]
@@ -91,6 +80,100 @@ pub struct PoolCollectingData {
     pub my_reward: String,
 }
 
+#[derive(Tabled)]
+pub struct BenchmarkCoreData {
+    #[tabled(rename = "Core")]
+    pub core: String,
+    #[tabled(rename = "Hashes")]
+    pub hashes: String,
+    #[tabled(rename = "Avg H/s")]
+    pub rate: String,
+    #[tabled(rename = "Warm Avg H/s")]
+    pub warm_rate: String,
+    #[tabled(rename = "Std Dev")]
+    pub std_dev: String,
+    #[tabled(rename = "Min H/s")]
+    pub min: String,
+    #[tabled(rename = "Max H/s")]
+    pub max: String,
+}
+
+#[derive(Tabled)]
+pub struct BenchmarkSweepData {
+    #[tabled(rename = "Cores")]
+    pub cores: String,
+    #[tabled(rename = "Pinned H/s")]
+    pub pinned: String,
+    #[tabled(rename = "Unpinned H/s")]
+    pub unpinned: String,
+    #[tabled(rename = "H/s per Core")]
+    pub per_core: String,
+    #[tabled(rename = "Scaling")]
+    pub scaling: String,
+    #[tabled(rename = "Curve")]
+    pub curve: String,
+    #[tabled(rename = "Throttling")]
+    pub throttling: String,
+}
+
+#[derive(Tabled)]
+pub struct DifficultyEstimateData {
+    #[tabled(rename = "Best Score")]
+    pub difficulty: String,
+    #[tabled(rename = "Chance")]
+    pub chance: String,
+    #[tabled(rename = "At Least")]
+    pub at_least: String,
+    #[tabled(rename = "Reward")]
+    pub reward: String,
+}
+
+#[derive(Tabled)]
+pub struct WalletAccountData {
+    #[tabled(rename = "Wallet")]
+    pub wallet: String,
+    #[tabled(rename = "ETH")]
+    pub eth: String,
+    #[tabled(rename = "Wallet BITZ")]
+    pub balance: String,
+    #[tabled(rename = "Proof BITZ")]
+    pub proof_balance: String,
+    #[tabled(rename = "Last Hash At")]
+    pub last_hash_at: String,
+    #[tabled(rename = "Lifetime Rewards")]
+    pub total_rewards: String,
+    #[tabled(rename = "Status")]
+    pub status: String,
+}
+
+#[derive(Tabled)]
+pub struct StakeData {
+    #[tabled(rename = "Mint")]
+    pub symbol: String,
+    #[tabled(rename = "Deposits")]
+    pub balance: String,
+    #[tabled(rename = "Rewards")]
+    pub rewards: String,
+    #[tabled(rename = "Weight")]
+    pub weight: String,
+    #[tabled(rename = "Share")]
+    pub share: String,
+    #[tabled(rename = "Est. APR")]
+    pub apr: String,
+}
+
+#[derive(Tabled)]
+pub struct StakeAccountData {
+    #[tabled(rename = "Address")]
+    pub address: String,
+    #[tabled(rename = "Authority")]
+    pub authority: String,
+    #[tabled(rename = "Deposits")]
+    pub balance: String,
+    #[tabled(rename = "Rewards")]
+    pub rewards: String,
+}
+
 pub trait TableSectionTitle {
     fn section_title(&mut self, row: usize, title: &str);
 }